@@ -1,11 +1,15 @@
 use serde::{Serialize, Serializer};
 
+use crate::util::archive::ExtractionError;
+
 pub mod binaries;
 pub mod cache;
 pub mod config;
 pub mod download;
 pub mod features;
 pub mod game;
+pub mod operations;
+pub mod profiles;
 pub mod support;
 pub mod util;
 pub mod versions;
@@ -13,7 +17,7 @@ pub mod versions;
 #[derive(Debug, thiserror::Error)]
 pub enum CommandError {
   #[error(transparent)]
-  Anyhow(#[from] anyhow::Error),
+  Anyhow(anyhow::Error),
   #[error(transparent)]
   IO(#[from] std::io::Error),
   #[error(transparent)]
@@ -30,6 +34,25 @@ pub enum CommandError {
   Support(String),
   #[error("{0}")]
   GameFeatures(String),
+  #[error("Checksum mismatch for {file}, expected {expected} but got {actual}")]
+  ChecksumMismatch {
+    file: String,
+    expected: String,
+    actual: String,
+  },
+  #[error(transparent)]
+  UnsafeArchive(#[from] ExtractionError),
+}
+
+impl From<anyhow::Error> for CommandError {
+  fn from(err: anyhow::Error) -> Self {
+    // Extraction goes through a few layers of context, an archive being refused shouldn't get
+    // lost in them
+    match err.downcast::<ExtractionError>() {
+      Ok(err) => Self::UnsafeArchive(err),
+      Err(err) => Self::Anyhow(err),
+    }
+  }
 }
 
 impl Serialize for CommandError {
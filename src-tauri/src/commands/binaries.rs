@@ -6,7 +6,7 @@ use std::os::windows::process::CommandExt;
 use std::{
   collections::HashMap,
   path::{Path, PathBuf},
-  process::Stdio,
+  process::{ExitStatus, Stdio},
   time::Instant,
 };
 use tokio::process::Command;
@@ -18,9 +18,13 @@ use tracing::{error, info, instrument, warn};
 
 use crate::{
   TAURI_APP,
-  config::{CommonConfigData, ExecutableLocation, LauncherConfig, SupportedGame},
+  config::{
+    CommonConfigData, DecompilerSettings, ExecutableLocation, LauncherConfig, SupportedGame,
+  },
   util::{
-    file::overwrite_dir,
+    activation,
+    cancellation::{self, OperationCancelled},
+    file::delete_dir,
     process::{create_log_file, create_std_log_file, watch_process},
   },
 };
@@ -76,12 +80,7 @@ fn copy_data_dir(
   config_info: &CommonConfigData,
   game_name: SupportedGame,
 ) -> Result<(), CommandError> {
-  let src_dir = config_info
-    .install_path
-    .join("versions")
-    .join("official")
-    .join(&config_info.active_version)
-    .join("data");
+  let src_dir = config_info.version_dir.join("data");
 
   let dst_dir = config_info
     .install_path
@@ -89,9 +88,14 @@ fn copy_data_dir(
     .join(game_name.to_string())
     .join("data");
 
+  if !src_dir.exists() {
+    return Ok(());
+  }
   info!("Copying {} into {}", src_dir.display(), dst_dir.display());
 
-  overwrite_dir(&src_dir, &dst_dir)?;
+  // Staged and swapped in as a whole, nothing of the previous version is left behind.  What was
+  // extracted from the ISO or built since isn't part of either and is kept.
+  activation::install(&src_dir, None, &dst_dir, &config_info.version_label())?;
   Ok(())
 }
 
@@ -116,6 +120,29 @@ fn get_data_dir(
   Ok(data_folder)
 }
 
+// Like `watch_process`, but if the operation is cancelled whatever it managed to write to
+// `partial_output` is removed so it can't be mistaken for a finished step
+async fn watch_tooling_process(
+  log_file: &mut tokio::fs::File,
+  child: &mut tokio::process::Child,
+  app_handle: &tauri::AppHandle,
+  operation_id: &str,
+  partial_output: &Path,
+) -> Result<ExitStatus, CommandError> {
+  match watch_process(log_file, child, app_handle, Some(operation_id)).await {
+    Ok(status) => Ok(status),
+    Err(err) => {
+      if err.is::<OperationCancelled>() {
+        info!("{err}, removing {}", partial_output.display());
+        if let Err(err) = delete_dir(partial_output) {
+          warn!("{err:#}");
+        }
+      }
+      Err(err.into())
+    }
+  }
+}
+
 #[instrument(skip(config))]
 #[tauri::command]
 pub async fn update_data_directory(
@@ -123,7 +150,7 @@ pub async fn update_data_directory(
   game_name: SupportedGame,
 ) -> Result<(), CommandError> {
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
   copy_data_dir(&config_info, game_name)?;
   Ok(())
 }
@@ -135,9 +162,12 @@ pub async fn extract_and_validate_iso(
   app_handle: tauri::AppHandle,
   path_to_iso: String,
   game_name: SupportedGame,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id = operation_id.unwrap_or_else(|| format!("extract:{game_name}"));
+  let _operation = cancellation::register(&operation_id);
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
   let data_folder = get_data_dir(&config_info, game_name, true)?;
   let exec_info = config_info.get_exec_location("extractor");
 
@@ -180,7 +210,15 @@ pub async fn extract_and_validate_iso(
   let mut log_file =
     create_log_file(&app_handle, format!("extractor-{game_name}.log"), true).await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let iso_data_folder = data_folder.join("iso_data").join(game_name.to_string());
+  let status = watch_tooling_process(
+    &mut log_file,
+    &mut child,
+    &app_handle,
+    &operation_id,
+    &iso_data_folder,
+  )
+  .await?;
   if status.success() {
     info!("extraction and validation was successful");
     return Ok(());
@@ -215,10 +253,35 @@ pub async fn run_decompiler(
   game_name: SupportedGame,
   truncate_logs: bool,
   use_decomp_settings: bool,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id = operation_id.unwrap_or_else(|| format!("decompile:{game_name}"));
+  let _operation = cancellation::register(&operation_id);
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
-  let data_folder = get_data_dir(&config_info, game_name, false)?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
+  let decompiler_settings = use_decomp_settings.then_some(&config_lock.decompiler_settings);
+  decompile(
+    &config_info,
+    decompiler_settings,
+    &app_handle,
+    path_to_iso,
+    game_name,
+    truncate_logs,
+    &operation_id,
+  )
+  .await
+}
+
+async fn decompile(
+  config_info: &CommonConfigData,
+  decompiler_settings: Option<&DecompilerSettings>,
+  app_handle: &tauri::AppHandle,
+  path_to_iso: Option<String>,
+  game_name: SupportedGame,
+  truncate_logs: bool,
+  operation_id: &str,
+) -> Result<(), CommandError> {
+  let data_folder = get_data_dir(config_info, game_name, false)?;
   let exec_info = config_info.get_exec_location("extractor");
 
   info!(
@@ -249,8 +312,7 @@ pub async fn run_decompiler(
     args.push(game_name.to_string());
   }
 
-  if use_decomp_settings {
-    let settings = &config_lock.decompiler_settings;
+  if let Some(settings) = decompiler_settings {
     let mut overrides = serde_json::Map::new();
 
     for (key, enabled) in [
@@ -285,20 +347,30 @@ pub async fn run_decompiler(
   let mut child = command.spawn().context("Failed to spawn decompiler")?;
 
   let mut log_file = create_log_file(
-    &app_handle,
+    app_handle,
     format!("extractor-{game_name}.log"),
     !truncate_logs,
   )
   .await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let decompiler_out_folder = data_folder
+    .join("decompiler_out")
+    .join(game_name.to_string());
+  let status = watch_tooling_process(
+    &mut log_file,
+    &mut child,
+    app_handle,
+    operation_id,
+    &decompiler_out_folder,
+  )
+  .await?;
   if status.success() {
     info!("decompilation was successful");
     return Ok(());
   }
 
   if let Some(code) = status.code() {
-    let message = get_error_code_message(&config_info, game_name, code);
+    let message = get_error_code_message(config_info, game_name, code);
     error!("decompilation was not successful. Code {code}");
     return Err(CommandError::BinaryExecution(message));
   }
@@ -312,9 +384,9 @@ pub async fn run_decompiler(
   }
 
   error!("decompilation was not successful. No status code.");
-  return Err(CommandError::BinaryExecution(
+  Err(CommandError::BinaryExecution(
     "Unexpected error occurred".to_owned(),
-  ));
+  ))
 }
 
 #[instrument(skip(config, app_handle))]
@@ -325,11 +397,33 @@ pub async fn run_compiler(
   path_to_iso: Option<String>,
   game_name: SupportedGame,
   truncate_logs: bool,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id = operation_id.unwrap_or_else(|| format!("compile:{game_name}"));
+  let _operation = cancellation::register(&operation_id);
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
+  compile(
+    &config_info,
+    &app_handle,
+    path_to_iso,
+    game_name,
+    truncate_logs,
+    &operation_id,
+  )
+  .await
+}
+
+async fn compile(
+  config_info: &CommonConfigData,
+  app_handle: &tauri::AppHandle,
+  path_to_iso: Option<String>,
+  game_name: SupportedGame,
+  truncate_logs: bool,
+  operation_id: &str,
+) -> Result<(), CommandError> {
   let exec_info = config_info.get_exec_location("extractor");
-  let data_folder = get_data_dir(&config_info, game_name, false)?;
+  let data_folder = get_data_dir(config_info, game_name, false)?;
 
   info!(
     "compiling using data folder: {}",
@@ -371,20 +465,28 @@ pub async fn run_compiler(
   let mut child = command.spawn().context("Failed to spawn compiler")?;
 
   let mut log_file = create_log_file(
-    &app_handle,
+    app_handle,
     format!("extractor-{game_name}.log"),
     !truncate_logs,
   )
   .await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let out_folder = data_folder.join("out").join(game_name.to_string());
+  let status = watch_tooling_process(
+    &mut log_file,
+    &mut child,
+    app_handle,
+    operation_id,
+    &out_folder,
+  )
+  .await?;
   if status.success() {
     info!("compilation was successful");
     return Ok(());
   }
 
   if let Some(code) = status.code() {
-    let message = get_error_code_message(&config_info, game_name, code);
+    let message = get_error_code_message(config_info, game_name, code);
     error!("compilation was not successful. Code {code}");
     return Err(anyhow::anyhow!(message).into());
   }
@@ -398,7 +500,41 @@ pub async fn run_compiler(
   }
 
   error!("compilation was not successful. No status code");
-  return Err(anyhow::anyhow!("compilation was not successful. No status code").into());
+  Err(anyhow::anyhow!("compilation was not successful. No status code").into())
+}
+
+/// Brings an installed game up to date with the tooling version it should be running on, the
+/// same steps as updating a game from the frontend
+pub async fn rebuild_game(
+  config_info: &CommonConfigData,
+  app_handle: &tauri::AppHandle,
+  game_name: SupportedGame,
+  operation_id: &str,
+) -> Result<(), CommandError> {
+  info!(
+    "Rebuilding {game_name} with version {}",
+    config_info.active_version
+  );
+  copy_data_dir(config_info, game_name)?;
+  decompile(
+    config_info,
+    None,
+    app_handle,
+    None,
+    game_name,
+    true,
+    operation_id,
+  )
+  .await?;
+  compile(
+    config_info,
+    app_handle,
+    None,
+    game_name,
+    false,
+    operation_id,
+  )
+  .await
 }
 
 #[instrument(skip(config))]
@@ -408,7 +544,7 @@ pub async fn open_repl(
   game_name: SupportedGame,
 ) -> Result<(), CommandError> {
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
   let data_folder = get_data_dir(&config_info, game_name, false)?;
   let exec_info = config_info.get_exec_location("goalc");
   let mut command;
@@ -505,7 +641,7 @@ pub async fn get_launch_game_string(
   game_name: SupportedGame,
 ) -> Result<String, CommandError> {
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
   let exec_info = config_info.get_exec_location("gk");
   let args = generate_launch_game_string(&config_info, game_name, false, true)?;
 
@@ -526,7 +662,7 @@ pub async fn launch_game(
   executable_location: Option<PathBuf>,
 ) -> Result<(), CommandError> {
   let config_lock = config.lock().await;
-  let config_info = config_lock.common_prelude()?;
+  let config_info = config_lock.common_prelude_for_game(game_name)?;
 
   let exec_info = if let Some(exec_path) = executable_location {
     ExecutableLocation {
@@ -605,6 +741,9 @@ async fn track_playtime(
 
   let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
   let mut config_lock = config.lock().await;
-  config_lock.update_seconds_played(game_name, elapsed_seconds)?;
+  config_lock.update(|config| {
+    config.update_seconds_played(game_name, elapsed_seconds);
+    Ok(())
+  })?;
   Ok(())
 }
@@ -1,8 +1,24 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::{CommandError, util::is_avx_supported};
-use crate::config::{LauncherConfig, SupportedGame};
+use super::{
+  CommandError,
+  game::rebuild_outdated_games,
+  util::{available_disk_space, is_avx_supported},
+};
+use crate::{
+  config::{
+    ChannelVersion, InstallProblem, LauncherConfig, SettingsBackup, SupportedGame, VersionChannel,
+  },
+  util::{
+    cancellation::{self, OperationCancelled},
+    file::{create_dir, delete_dir, dir_contents_match},
+    progress::{ProgressReporter, ProgressStage},
+    tooling,
+  },
+};
+use anyhow::{Context, anyhow};
 use semver::Version;
+use tauri::Manager;
 use tracing::instrument;
 
 #[instrument(skip(config))]
@@ -11,9 +27,14 @@ pub async fn reset_to_defaults(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.reset_to_defaults().map_err(|_| {
-    CommandError::Configuration("Unable to reset configuration to defaults".to_owned())
-  })?;
+  config_lock
+    .update(|config| {
+      config.reset_to_defaults();
+      Ok(())
+    })
+    .map_err(|_| {
+      CommandError::Configuration("Unable to reset configuration to defaults".to_owned())
+    })?;
   Ok(())
 }
 
@@ -25,22 +46,106 @@ pub async fn set_game_installed(
   installed: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_game_installed(game_name, installed)?;
+  config_lock.update(|config| {
+    config.set_game_installed(game_name, installed);
+    Ok(())
+  })?;
+  Ok(())
+}
+
+// A version that won't run is never switched to.  The config isn't locked while probing, the
+// binaries get a few seconds each to respond.
+async fn probe_version(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  channel: VersionChannel,
+  version: &str,
+) -> Result<(), CommandError> {
+  let version_dir = config.lock().await.version_dir(channel, version)?;
+  tooling::probe_and_record(&version_dir)
+    .await
+    .map_err(|err| {
+      tracing::error!("Unable to use version {channel}/{version}: {err:?}");
+      CommandError::BinaryExecution(format!("Unable to use version {version}: {err:#}"))
+    })?;
   Ok(())
 }
 
 #[instrument(skip(config))]
 #[tauri::command]
+pub async fn set_game_pinned_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  game_name: SupportedGame,
+  channel: VersionChannel,
+  version: Option<String>,
+) -> Result<(), CommandError> {
+  if let Some(version) = &version {
+    probe_version(&config, channel, version).await?;
+  }
+  config
+    .lock()
+    .await
+    .update(|config| config.pin_game_version(game_name, channel, version))
+    .map_err(|err| {
+      tracing::error!("Unable to pin game version: {:?}", err);
+      CommandError::Configuration(format!("Unable to pin game version: {err}"))
+    })?;
+  Ok(())
+}
+
+#[instrument(skip(config, app_handle))]
+#[tauri::command]
 pub async fn set_active_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+  channel: VersionChannel,
   version: String,
 ) -> Result<(), CommandError> {
+  probe_version(&config, channel, &version).await?;
   let mut config_lock = config.lock().await;
-  config_lock.set_active_version(Some(version))?;
-  config_lock.copy_active_binaries()?;
+  config_lock.activate_version(channel, version)?;
+
+  if config_lock.auto_update_games && !config_lock.outdated_games().is_empty() {
+    drop(config_lock);
+    // Rebuilding takes a while, don't hold up switching versions for it
+    tauri::async_runtime::spawn(async move {
+      let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+      rebuild_outdated_games(&config, &app_handle).await;
+    });
+  }
   Ok(())
 }
 
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn get_previous_active_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Option<ChannelVersion>, CommandError> {
+  Ok(config.lock().await.previous_active_version())
+}
+
+/// Switches back to the version that was active before the current one
+#[instrument(skip(config, app_handle))]
+#[tauri::command]
+pub async fn rollback_active_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+) -> Result<ChannelVersion, CommandError> {
+  let mut config_lock = config.lock().await;
+  let restored = config_lock.rollback_active_version().map_err(|err| {
+    tracing::error!("Unable to roll back the active version: {err:?}");
+    CommandError::Configuration(format!("Unable to roll back the active version: {err:#}"))
+  })?;
+
+  if config_lock.auto_update_games && !config_lock.outdated_games().is_empty() {
+    drop(config_lock);
+    tauri::async_runtime::spawn(async move {
+      let config = app_handle.state::<tokio::sync::Mutex<LauncherConfig>>();
+      rebuild_outdated_games(&config, &app_handle).await;
+    });
+  }
+  Ok(restored)
+}
+
 #[instrument(skip(config))]
 #[tauri::command]
 pub async fn get_launcher_config(
@@ -57,10 +162,12 @@ pub async fn set_texture_packs(
   texture_packs: Vec<String>,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock
-    .get_supported_game_config_mut(game_name)
-    .set_texture_packs(texture_packs);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config
+      .get_supported_game_config_mut(game_name)
+      .set_texture_packs(texture_packs);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -71,10 +178,240 @@ pub async fn set_install_directory(
   new_dir: PathBuf,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_install_directory(new_dir).map_err(|err| {
-    tracing::error!("Unable to persist installation directory: {:?}", err);
-    CommandError::Configuration("Unable to persist installation directory".to_owned())
-  })?;
+  config_lock
+    .update(|config| config.set_install_directory(new_dir))
+    .map_err(|err| {
+      tracing::error!("Unable to persist installation directory: {:?}", err);
+      CommandError::Configuration("Unable to persist installation directory".to_owned())
+    })?;
+  Ok(())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn diagnose_install(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<InstallProblem>, CommandError> {
+  let config_lock = config.lock().await;
+  config_lock.diagnose_install().map_err(|err| {
+    tracing::error!("Unable to diagnose installation: {:?}", err);
+    CommandError::Configuration(format!("Unable to diagnose installation: {err}"))
+  })
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn repair_install(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<InstallProblem>, CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.repair_install().map_err(|err| {
+    tracing::error!("Unable to repair installation: {:?}", err);
+    CommandError::Configuration(format!("Unable to repair installation: {err}"))
+  })
+}
+
+// Everything the launcher manages inside of the installation directory
+const INSTALL_DIR_ENTRIES: [&str; 3] = ["versions", "active", "features"];
+
+// Fast path for when both directories are on the same drive, returns false if anything could not
+// be renamed (in which case everything is put back where it was)
+fn rename_install_entries(old_dir: &Path, new_dir: &Path, entries: &[&str]) -> bool {
+  for (index, entry) in entries.iter().enumerate() {
+    if let Err(err) = std::fs::rename(old_dir.join(entry), new_dir.join(entry)) {
+      tracing::info!(
+        "Unable to rename '{entry}' into the new installation directory, copying instead: {err}"
+      );
+      for moved in &entries[..index] {
+        if let Err(err) = std::fs::rename(new_dir.join(moved), old_dir.join(moved)) {
+          tracing::error!("Unable to move '{moved}' back to the old installation directory: {err}");
+        }
+      }
+      return false;
+    }
+  }
+  true
+}
+
+fn copy_install_entries(
+  old_dir: &Path,
+  new_dir: &Path,
+  entries: &[&str],
+  operation_id: &str,
+) -> anyhow::Result<()> {
+  let sources: Vec<PathBuf> = entries.iter().map(|entry| old_dir.join(entry)).collect();
+  let total_bytes = sources
+    .iter()
+    .map(fs_extra::dir::get_size)
+    .sum::<Result<u64, _>>()
+    .context("Unable to determine the size of the installation directory")?;
+
+  let available_bytes = available_disk_space(new_dir)?;
+  if available_bytes < total_bytes {
+    anyhow::bail!(
+      "Not enough free space in {}, {} MB required but only {} MB available",
+      new_dir.display(),
+      total_bytes / 1024 / 1024,
+      available_bytes / 1024 / 1024
+    );
+  }
+
+  tracing::info!(
+    "Copying {} MB from {} into {}",
+    total_bytes / 1024 / 1024,
+    old_dir.display(),
+    new_dir.display()
+  );
+  let mut reporter = ProgressReporter::new(
+    Some(operation_id),
+    ProgressStage::Relocate,
+    Some(total_bytes),
+  );
+  let mut copied_bytes = 0;
+  fs_extra::copy_items_with_progress(
+    &sources,
+    new_dir,
+    &fs_extra::dir::CopyOptions::new(),
+    |progress| {
+      reporter.advance(progress.copied_bytes.saturating_sub(copied_bytes));
+      copied_bytes = copied_bytes.max(progress.copied_bytes);
+      if reporter.is_cancelled() {
+        fs_extra::dir::TransitProcessResult::Abort
+      } else {
+        fs_extra::dir::TransitProcessResult::ContinueOrAbort
+      }
+    },
+  )
+  .context("Unable to copy the installation directory")?;
+  if reporter.is_cancelled() {
+    Err(OperationCancelled(operation_id.to_owned()))?;
+  }
+  reporter.finish();
+
+  for entry in entries {
+    if !dir_contents_match(old_dir.join(entry), new_dir.join(entry))? {
+      anyhow::bail!("Copy of '{entry}' does not match the original");
+    }
+  }
+  Ok(())
+}
+
+// Resolves links and `..` in `path`, which doesn't have to exist yet
+fn canonical_path(path: &Path) -> std::io::Result<PathBuf> {
+  let mut existing = path;
+  let mut missing = Vec::new();
+  while !existing.exists() {
+    match (existing.parent(), existing.file_name()) {
+      (Some(parent), Some(name)) => {
+        missing.push(name);
+        existing = parent;
+      }
+      _ => break,
+    }
+  }
+  let mut canonical = existing.canonicalize()?;
+  canonical.extend(missing.into_iter().rev());
+  Ok(canonical)
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn relocate_install_directory(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  new_dir: PathBuf,
+  operation_id: Option<String>,
+) -> Result<(), CommandError> {
+  let operation_id = operation_id.unwrap_or_else(|| "relocate".to_owned());
+  let _operation = cancellation::register(&operation_id);
+
+  // The config isn't locked while files are moved around, copying a large install can take a
+  // long time
+  let old_dir = config.lock().await.install_dir()?;
+  let (canonical_old_dir, canonical_new_dir) =
+    (canonical_path(&old_dir)?, canonical_path(&new_dir)?);
+  if canonical_new_dir == canonical_old_dir {
+    return Ok(());
+  }
+  if canonical_new_dir.starts_with(&canonical_old_dir)
+    || canonical_old_dir.starts_with(&canonical_new_dir)
+  {
+    return Err(CommandError::Configuration(
+      "The installation directory can't be moved into itself".to_owned(),
+    ));
+  }
+
+  create_dir(&new_dir)?;
+  let entries: Vec<&str> = INSTALL_DIR_ENTRIES
+    .into_iter()
+    .filter(|entry| old_dir.join(entry).exists())
+    .collect();
+  if let Some(existing) = entries.iter().find(|entry| new_dir.join(entry).exists()) {
+    return Err(CommandError::Configuration(format!(
+      "{} already contains a '{existing}' folder",
+      new_dir.display()
+    )));
+  }
+
+  // Nothing below may leave the old location unusable, until the config is switched over it
+  // remains the source of truth
+  let renamed = rename_install_entries(&old_dir, &new_dir, &entries);
+  if !renamed {
+    let copy_result = {
+      let (old_dir, new_dir) = (old_dir.clone(), new_dir.clone());
+      let (entries, operation_id) = (entries.clone(), operation_id.clone());
+      tokio::task::spawn_blocking(move || {
+        copy_install_entries(&old_dir, &new_dir, &entries, &operation_id)
+      })
+      .await
+      .map_err(|err| anyhow!("Relocation task failed: {err}"))
+      .and_then(|result| result)
+    };
+    if let Err(err) = copy_result {
+      tracing::error!("Unable to relocate installation directory: {err:?}");
+      for entry in &entries {
+        let _ = delete_dir(new_dir.join(entry));
+      }
+      return Err(CommandError::Configuration(format!(
+        "Unable to relocate installation directory: {err}"
+      )));
+    }
+  }
+
+  let mut config_lock = config.lock().await;
+  let update_result = if config_lock.installation_dir.as_ref() == Some(&old_dir) {
+    config_lock.update(|config| config.set_relocated_install_directory(new_dir.clone()))
+  } else {
+    Err(anyhow!(
+      "The installation directory was changed while it was being moved"
+    ))
+  };
+  drop(config_lock);
+  if let Err(err) = update_result {
+    tracing::error!("Unable to persist relocated installation directory: {err:?}");
+    if renamed {
+      rename_install_entries(&new_dir, &old_dir, &entries);
+    } else {
+      for entry in &entries {
+        let _ = delete_dir(new_dir.join(entry));
+      }
+    }
+    return Err(CommandError::Configuration(format!(
+      "Unable to persist installation directory: {err}"
+    )));
+  }
+
+  if !renamed {
+    for entry in &entries {
+      if let Err(err) = delete_dir(old_dir.join(entry)) {
+        tracing::warn!("Unable to clean up the old installation directory: {err:?}");
+      }
+    }
+  }
+  tracing::info!(
+    "Installation directory relocated from {} to {}",
+    old_dir.display(),
+    new_dir.display()
+  );
   Ok(())
 }
 
@@ -85,8 +422,10 @@ pub async fn is_avx_requirement_met(
 ) -> Result<bool, CommandError> {
   let mut config_lock = config.lock().await;
   let avx_supported = is_avx_supported().await;
-  config_lock.requirements.set_avx(avx_supported);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.requirements.set_avx(avx_supported);
+    Ok(())
+  })?;
   Ok(avx_supported)
 }
 
@@ -97,8 +436,10 @@ pub async fn set_bypass_requirements(
   bypass: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.requirements.set_bypass_requirements(bypass);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.requirements.set_bypass_requirements(bypass);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -109,8 +450,10 @@ pub async fn set_locale(
   locale: String,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_locale(locale)?;
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.set_locale(locale);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -122,7 +465,10 @@ pub async fn update_mod_sources(
   add: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.update_mod_sources(source, add)?;
+  config_lock.update(|config| {
+    config.update_mod_sources(source, add);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -133,7 +479,40 @@ pub async fn set_auto_update_games(
   auto_update: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_auto_update_games(auto_update)?;
+  config_lock.update(|config| {
+    config.set_auto_update_games(auto_update);
+    Ok(())
+  })?;
+  Ok(())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn set_versions_to_keep(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  count: u32,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .update(|config| config.set_versions_to_keep(count))
+    .map_err(|err| {
+      tracing::error!("Unable to change how many versions to keep: {err:?}");
+      CommandError::Configuration(format!("Unable to change how many versions to keep: {err}"))
+    })?;
+  Ok(())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn set_release_index_url(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  url: Option<String>,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.update(|config| {
+    config.set_release_index_url(url);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -144,7 +523,10 @@ pub async fn set_check_for_latest_mod_version(
   check: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_check_for_latest_mod_version(check)?;
+  config_lock.update(|config| {
+    config.set_check_for_latest_mod_version(check);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -155,7 +537,10 @@ pub async fn set_delete_previous_versions(
   delete: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_delete_previous_versions(delete)?;
+  config_lock.update(|config| {
+    config.set_delete_previous_versions(delete);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -166,10 +551,10 @@ pub async fn set_rip_levels(
   enabled: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock
-    .decompiler_settings
-    .set_rip_levels_enabled(enabled);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.decompiler_settings.set_rip_levels_enabled(enabled);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -180,10 +565,12 @@ pub async fn set_rip_collision(
   enabled: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock
-    .decompiler_settings
-    .set_rip_collision_enabled(enabled);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config
+      .decompiler_settings
+      .set_rip_collision_enabled(enabled);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -194,10 +581,10 @@ pub async fn set_rip_textures(
   enabled: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock
-    .decompiler_settings
-    .set_rip_textures_enabled(enabled);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.decompiler_settings.set_rip_textures_enabled(enabled);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -208,10 +595,12 @@ pub async fn set_rip_streamed_audio(
   enabled: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock
-    .decompiler_settings
-    .set_rip_streamed_audio_enabled(enabled);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config
+      .decompiler_settings
+      .set_rip_streamed_audio_enabled(enabled);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -238,14 +627,14 @@ pub async fn is_opengl_requirement_met(
   // Check the active tooling version, if it's above 0.1.38 we can use the new
   // built-in gpu testing feature
   // If not, we have to use the spotty heuristic
-  let active_version = config_lock
-    .active_version
-    .as_ref()
-    .ok_or(CommandError::Configuration(
+  if config_lock.active_version.is_none() {
+    return Err(CommandError::Configuration(
       "No active version set, can't perform operation".to_owned(),
-    ))?;
+    ));
+  }
   // If we can't determine the version, assume it's too old
-  let tooling_version = Version::parse(active_version.strip_prefix('v').unwrap_or(active_version))
+  let tooling_version = config_lock
+    .active_tooling_version()
     .unwrap_or(Version::new(0, 1, 37));
   if tooling_version.major == 0 && tooling_version.minor <= 1 && tooling_version.patch < 38 {
     // Assume it's fine
@@ -259,8 +648,10 @@ pub async fn is_opengl_requirement_met(
   // Note: if the minimum vcc runtime requirement (windows only) isn't met then run_game_gpu_test will fail right here with a non zero error code
   // what looks like a GPU test failure is actually just the game not launching
   let test_result = crate::util::game_tests::run_game_gpu_test(&config_lock, &app_handle).await?;
-  config_lock.requirements.set_opengl(test_result.success);
-  config_lock.save_config()?;
+  config_lock.update(|config| {
+    config.requirements.set_opengl(test_result.success);
+    Ok(())
+  })?;
   Ok(test_result.success)
 }
 
@@ -273,7 +664,10 @@ pub async fn cleanup_enabled_texture_packs(
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
   config_lock
-    .cleanup_game_enabled_texture_packs(game_name, cleanup_list)
+    .update(|config| {
+      config.cleanup_game_enabled_texture_packs(game_name, cleanup_list);
+      Ok(())
+    })
     .map_err(|_| {
       CommandError::Configuration("Unable to cleanup enabled texture packs".to_owned())
     })?;
@@ -287,7 +681,10 @@ pub async fn set_hide_beta_alerts(
   hide: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_hide_beta_alerts(hide)?;
+  config_lock.update(|config| {
+    config.set_hide_beta_alerts(hide);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -298,7 +695,10 @@ pub async fn set_hide_gamename(
   hide: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_hide_gamename(hide)?;
+  config_lock.update(|config| {
+    config.set_hide_gamename(hide);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -309,7 +709,10 @@ pub async fn set_hide_playtime(
   hide: bool,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  config_lock.set_hide_playtime(hide)?;
+  config_lock.update(|config| {
+    config.set_hide_playtime(hide);
+    Ok(())
+  })?;
   Ok(())
 }
 
@@ -320,14 +723,14 @@ pub async fn does_active_tooling_version_support_game(
   game_name: SupportedGame,
 ) -> Result<bool, CommandError> {
   let config_lock = config.lock().await;
-  let version_str = if let Some(v) = &config_lock.active_version {
-    v.strip_prefix('v').unwrap_or(v)
-  } else {
+  if config_lock.active_version.is_none() {
     tracing::warn!("No active tooling version set, can't check the game supports it!");
     return Ok(false);
-  };
+  }
 
-  let tooling_version = Version::parse(version_str).unwrap_or_else(|_| Version::new(0, 0, 1));
+  let tooling_version = config_lock
+    .active_tooling_version()
+    .unwrap_or_else(|| Version::new(0, 0, 1));
   let supported = match game_name {
     SupportedGame::Jak1 => true,
     SupportedGame::Jak2 => tooling_version.minor >= 2,
@@ -347,9 +750,10 @@ pub async fn does_active_tooling_version_meet_minimum(
   minimum_major: u64,
 ) -> Result<bool, CommandError> {
   let config_lock = config.lock().await;
-  if let Some(version) = &config_lock.active_version {
-    let tooling_version = Version::parse(version.strip_prefix('v').unwrap_or(version))
-      .unwrap_or_else(|_| Version::new(0, 0, 0));
+  if config_lock.active_version.is_some() {
+    let tooling_version = config_lock
+      .active_tooling_version()
+      .unwrap_or_else(|| Version::new(0, 0, 0));
     let compare_version = Version::new(minimum_major, minimum_minor, minimum_patch);
     Ok(tooling_version >= compare_version)
   } else {
@@ -357,3 +761,26 @@ pub async fn does_active_tooling_version_meet_minimum(
     Ok(false)
   }
 }
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn list_settings_backups(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<SettingsBackup>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.list_backups()?)
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn restore_settings_backup(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  file_name: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock.restore_backup(&file_name).map_err(|err| {
+    tracing::error!("Unable to restore settings backup: {:?}", err);
+    CommandError::Configuration(format!("Unable to restore settings backup: {err}"))
+  })?;
+  Ok(())
+}
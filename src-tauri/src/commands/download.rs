@@ -2,13 +2,19 @@ use std::path::PathBuf;
 
 use tracing::instrument;
 
-use crate::util::network;
+use crate::util::{cancellation, network};
 
 use super::CommandError;
 
 #[instrument]
 #[tauri::command]
-pub async fn download_file(url: String, destination: PathBuf) -> Result<(), CommandError> {
-  network::download_file(&url, &destination).await?;
+pub async fn download_file(
+  url: String,
+  destination: PathBuf,
+  operation_id: Option<String>,
+) -> Result<(), CommandError> {
+  let operation_id = operation_id.unwrap_or_else(|| destination.display().to_string());
+  let _operation = cancellation::register(&operation_id);
+  network::download_file(&url, &destination, Some(&operation_id)).await?;
   Ok(())
 }
@@ -0,0 +1,132 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+use tracing::instrument;
+use ts_rs::TS;
+
+use crate::{
+  commands::CommandError,
+  util::{
+    archive::{ArchiveEntry, ArchiveFormat, common_top_dir},
+    tar::visit_archive_entries,
+  },
+};
+
+// Texture packs ship a `metadata.json`, mods an `_metadata.json`
+const METADATA_FILE_NAMES: [&str; 2] = ["metadata.json", "_metadata.json"];
+const MAX_METADATA_SIZE: u64 = 1024 * 1024;
+
+/// What an archive would install, so it can be confirmed before anything is extracted
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ArchiveInspection {
+  pub format: ArchiveFormat,
+  pub entries: Vec<ArchiveEntry>,
+  pub total_size: u64,
+  /// The folder everything is in, if there is only one
+  pub top_level_folder: Option<String>,
+  pub has_gk: bool,
+  pub has_extractor: bool,
+  pub has_goalc: bool,
+  pub metadata_path: Option<String>,
+  /// The raw contents of the metadata file, it isn't validated
+  pub metadata: Option<String>,
+  /// Every `texture_replacements` folder in the archive
+  pub texture_replacement_paths: Vec<String>,
+}
+
+fn file_name(path: &str) -> &str {
+  path.rsplit('/').next().unwrap_or(path)
+}
+
+fn inspect(archive_path: &Path) -> Result<ArchiveInspection> {
+  let format = ArchiveFormat::detect(archive_path)?;
+  let mut entries = Vec::new();
+  // The metadata closest to the root wins
+  let mut metadata: Option<(usize, String, String)> = None;
+  visit_archive_entries(archive_path, format, |entry, contents| {
+    let depth = entry.path.matches('/').count();
+    if !entry.is_dir
+      && entry.size <= MAX_METADATA_SIZE
+      && METADATA_FILE_NAMES.contains(&file_name(&entry.path))
+      && metadata
+        .as_ref()
+        .is_none_or(|(closest_depth, ..)| depth < *closest_depth)
+    {
+      let mut bytes = Vec::new();
+      contents.take(MAX_METADATA_SIZE).read_to_end(&mut bytes)?;
+      let text = String::from_utf8_lossy(&bytes).into_owned();
+      metadata = Some((depth, entry.path.clone(), text));
+    }
+    entries.push(entry.clone());
+    Ok(())
+  })?;
+
+  let top_level_folder = common_top_dir(entries.iter().map(|entry| Path::new(&entry.path)))
+    .map(|folder| folder.to_string_lossy().into_owned());
+  let root = top_level_folder
+    .as_ref()
+    .map(|folder| format!("{folder}/"))
+    .unwrap_or_default();
+  let has_binary = |binary: &str| {
+    entries.iter().any(|entry| {
+      !entry.is_dir
+        && entry
+          .path
+          .strip_prefix(&root)
+          .is_some_and(|path| path == binary || path == format!("{binary}.exe"))
+    })
+  };
+
+  let mut texture_replacement_paths: Vec<String> = entries
+    .iter()
+    .filter_map(|entry| {
+      let parts: Vec<&str> = entry.path.split('/').collect();
+      let index = parts
+        .iter()
+        .position(|part| *part == "texture_replacements")?;
+      Some(parts[..=index].join("/"))
+    })
+    .collect();
+  texture_replacement_paths.sort();
+  texture_replacement_paths.dedup();
+
+  let (metadata_path, metadata) = match metadata {
+    Some((_, path, text)) => (Some(path), Some(text)),
+    None => (None, None),
+  };
+  Ok(ArchiveInspection {
+    format,
+    total_size: entries
+      .iter()
+      .filter(|entry| !entry.is_dir)
+      .map(|entry| entry.size)
+      .sum(),
+    top_level_folder,
+    has_gk: has_binary("gk"),
+    has_extractor: has_binary("extractor"),
+    has_goalc: has_binary("goalc"),
+    metadata_path,
+    metadata,
+    texture_replacement_paths,
+    entries,
+  })
+}
+
+#[instrument]
+#[tauri::command]
+pub async fn inspect_archive(archive_path: PathBuf) -> Result<ArchiveInspection, CommandError> {
+  // Going through a compressed tarball can take a while
+  let inspection = tokio::task::spawn_blocking(move || inspect(&archive_path))
+    .await
+    .map_err(|err| anyhow::anyhow!("Inspection task failed: {err}"))
+    .and_then(|result| result)
+    .map_err(|err| {
+      tracing::error!("Unable to inspect archive: {err:?}");
+      CommandError::GameFeatures(format!("Unable to inspect archive: {err:#}"))
+    })?;
+  Ok(inspection)
+}
@@ -1,2 +1,3 @@
+pub mod archives;
 pub mod mods;
 pub mod texture_packs;
@@ -17,13 +17,28 @@ use crate::{
   commands::{CommandError, binaries::format_exit_code},
   config::{ExecutableLocation, LauncherConfig, SupportedGame},
   util::{
-    file::{delete_dir, to_image_base64},
+    cancellation::{self, OperationCancelled},
+    file::{delete_dir, replace_dir, staging_dir, to_image_base64},
     network::download_file,
     process::{create_log_file, create_std_log_file, watch_process},
     tar::{extract_and_delete_archive, extract_archive},
+    tooling,
   },
 };
 
+// Mods run with their own binaries, a bundle without them can't be installed.  They're looked for
+// once the folder bundles are often zipped up in has been stripped, where the mod runs them from.
+fn validate_mod_bundle(mod_dir: &Path) -> Result<(), CommandError> {
+  let missing = tooling::missing_binaries(mod_dir);
+  if !missing.is_empty() {
+    return Err(CommandError::GameFeatures(format!(
+      "The mod bundle is missing {}",
+      missing.join(", ")
+    )));
+  }
+  Ok(())
+}
+
 #[instrument(skip(config))]
 #[tauri::command]
 pub async fn extract_new_mod(
@@ -31,13 +46,14 @@ pub async fn extract_new_mod(
   game_name: SupportedGame,
   bundle_path: PathBuf,
   mod_source: String,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
   let install_path = {
     let config_lock = config.lock().await;
     config_lock.install_dir()?
   };
 
-  // The name of the zip becomes the folder, if one already exists it will be deleted!
+  // The name of the zip becomes the folder, if one already exists it will be replaced!
   let mod_name = bundle_path
     .file_stem()
     .and_then(|stem| stem.to_str())
@@ -48,11 +64,18 @@ pub async fn extract_new_mod(
     .join("features")
     .join(game_name.to_string())
     .join("mods")
-    .join(mod_source)
+    .join(&mod_source)
     .join(mod_name);
 
-  delete_dir(&destination_dir)?;
-  extract_archive(&bundle_path, &destination_dir)?;
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("mod:{game_name}/{mod_source}/{mod_name}"));
+  let _operation = cancellation::register(&operation_id);
+  // The installed mod stays untouched until the new one has been extracted in full
+  let staging_dir = staging_dir(&install_path, ".mod-")?;
+  let staged_mod_dir = staging_dir.path().join("contents");
+  extract_archive(&bundle_path, &staged_mod_dir, true, Some(&operation_id))?;
+  validate_mod_bundle(&staged_mod_dir)?;
+  replace_dir(&staged_mod_dir, &destination_dir)?;
 
   Ok(())
 }
@@ -66,7 +89,11 @@ pub async fn download_and_extract_new_mod(
   download_url: String,
   mod_name: String,
   source_name: String,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("mod:{game_name}/{source_name}/{mod_name}"));
+  let _operation = cancellation::register(&operation_id);
   let install_path = {
     let config_lock = config.lock().await;
     config_lock.install_dir()?
@@ -86,11 +113,14 @@ pub async fn download_and_extract_new_mod(
     .filter(|s| !s.is_empty())
     .context("Invalid URL: missing filename")?;
 
-  let download_path = &destination_dir.join(filename);
+  // The installed mod stays untouched until the new one has been downloaded and extracted in full
+  let staging_dir = staging_dir(&install_path, ".mod-")?;
+  let download_path = staging_dir.path().join("download").join(filename);
+  let staged_mod_dir = staging_dir.path().join("contents");
 
-  delete_dir(&destination_dir)?;
-  download_file(&download_url, download_path).await?;
-  extract_and_delete_archive(download_path, &destination_dir, false)?;
+  download_file(&download_url, &download_path, Some(&operation_id)).await?;
+  extract_and_delete_archive(&download_path, &staged_mod_dir, true, Some(&operation_id))?;
+  validate_mod_bundle(&staged_mod_dir)?;
 
   // Persist the info about the mod to the disk in the event that the mod source is removed / etc
   let mod_info = {
@@ -104,13 +134,14 @@ pub async fn download_and_extract_new_mod(
       .ok_or_else(|| anyhow::anyhow!("Unable to find mod {} in source {}", mod_name, source_name))?
   };
 
-  let metadata_path = destination_dir.join("_metadata.json");
+  let metadata_path = staged_mod_dir.join("_metadata.json");
   let file = fs::File::create(&metadata_path)?;
 
   tracing::info!("saving mod info to: {}", &metadata_path.display());
   serde_json::to_writer_pretty(file, &mod_info)
     .map_err(|e| anyhow::anyhow!("Unable to save mod metadata: {}", e))?;
 
+  replace_dir(&staged_mod_dir, &destination_dir)?;
   Ok(())
 }
 
@@ -215,7 +246,11 @@ pub async fn extract_iso_for_mod_install(
   mod_name: String,
   source_name: String,
   path_to_iso: String,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("extract:{game_name}/{source_name}/{mod_name}"));
+  let _operation = cancellation::register(&operation_id);
   let install_path = {
     let config_lock = config.lock().await;
     config_lock.install_dir()?
@@ -267,7 +302,21 @@ pub async fn extract_iso_for_mod_install(
   )
   .await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let status =
+    match watch_process(&mut log_file, &mut child, &app_handle, Some(&operation_id)).await {
+      Ok(status) => status,
+      Err(err) => {
+        if err.is::<OperationCancelled>() {
+          let partial_output = iso_extraction_dir.join(game_name.to_string());
+          tracing::info!("{err}, removing {}", partial_output.display());
+          // The cancel is what gets reported, even if the cleanup doesn't go through
+          if let Err(err) = delete_dir(partial_output) {
+            tracing::warn!("{err:#}");
+          }
+        }
+        return Err(err.into());
+      }
+    };
   if status.success() {
     tracing::info!("extraction and validation was successful");
     return Ok(());
@@ -290,7 +339,11 @@ pub async fn decompile_for_mod_install(
   game_name: SupportedGame,
   mod_name: String,
   source_name: String,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("decompile:{game_name}/{source_name}/{mod_name}"));
+  let _operation = cancellation::register(&operation_id);
   let install_path = {
     let config_lock = config.lock().await;
     config_lock.install_dir()?
@@ -336,7 +389,7 @@ pub async fn decompile_for_mod_install(
   let mut log_file =
     create_log_file(&app_handle, format!("extractor-{game_name}.log"), false).await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let status = watch_process(&mut log_file, &mut child, &app_handle, Some(&operation_id)).await?;
   if status.success() {
     tracing::info!("decompilation was successful");
     return Ok(());
@@ -359,7 +412,11 @@ pub async fn compile_for_mod_install(
   game_name: SupportedGame,
   mod_name: String,
   source_name: String,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("compile:{game_name}/{source_name}/{mod_name}"));
+  let _operation = cancellation::register(&operation_id);
   let install_path = {
     let config_lock = config.lock().await;
     config_lock.install_dir()?
@@ -404,7 +461,7 @@ pub async fn compile_for_mod_install(
   let mut log_file =
     create_log_file(&app_handle, format!("extractor-{game_name}.log"), false).await?;
 
-  let status = watch_process(&mut log_file, &mut child, &app_handle).await?;
+  let status = watch_process(&mut log_file, &mut child, &app_handle, Some(&operation_id)).await?;
   if status.success() {
     tracing::info!("compilation was successful");
     return Ok(());
@@ -437,7 +494,10 @@ pub async fn save_mod_install_info(
     version_name
   );
   config_lock
-    .add_mod(game_name, source_name, version_name, mod_name)
+    .update(|config| {
+      config.add_mod(game_name, source_name, version_name, mod_name);
+      Ok(())
+    })
     .map_err(|err| {
       tracing::error!("Unable to remove mod source: {:?}", err);
       CommandError::Configuration("Unable to remove mod source".to_owned())
@@ -568,7 +628,10 @@ pub async fn uninstall_mod(
     std::fs::remove_dir_all(mod_dir)?;
   }
   config_lock
-    .uninstall_mod(game_name, source_name, mod_name)
+    .update(|config| {
+      config.uninstall_mod(game_name, source_name, mod_name);
+      Ok(())
+    })
     .map_err(|err| CommandError::GameFeatures(format!("Unable to uninstall mod: {}", err)))?;
   Ok(())
 }
@@ -721,3 +784,51 @@ pub async fn open_repl_for_mod(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A bundle as mods usually ship them, everything in a folder named after the mod
+  fn bundle(dir: &Path, binaries: &[&str]) -> PathBuf {
+    let path = dir.join("my-mod.tar");
+    let mut builder = tar::Builder::new(fs::File::create(&path).unwrap());
+    let files = binaries
+      .iter()
+      .map(|binary| tooling::binary_file_name(binary))
+      .chain(["data/game/goal_src/mod.gc".to_owned()]);
+    for file in files {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(4);
+      header.set_mode(0o755);
+      header.set_cksum();
+      builder
+        .append_data(&mut header, format!("my-mod/{file}"), &b"test"[..])
+        .unwrap();
+    }
+    builder.finish().unwrap();
+    path
+  }
+
+  #[test]
+  fn accepts_bundles_wrapped_in_a_folder() {
+    let dir = tempfile::tempdir().unwrap();
+    let bundle_path = bundle(dir.path(), &tooling::BINARIES);
+    let mod_dir = dir.path().join("mod");
+    extract_archive(&bundle_path, &mod_dir, true, None).unwrap();
+    validate_mod_bundle(&mod_dir).unwrap();
+    assert!(mod_dir.join(tooling::binary_file_name("gk")).is_file());
+  }
+
+  #[test]
+  fn refuses_bundles_without_binaries() {
+    let dir = tempfile::tempdir().unwrap();
+    let bundle_path = bundle(dir.path(), &["gk", "extractor"]);
+    let mod_dir = dir.path().join("mod");
+    extract_archive(&bundle_path, &mod_dir, true, None).unwrap();
+    assert!(matches!(
+      validate_mod_bundle(&mod_dir),
+      Err(CommandError::GameFeatures(_))
+    ));
+  }
+}
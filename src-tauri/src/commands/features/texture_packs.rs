@@ -7,7 +7,8 @@ use crate::{
   commands::CommandError,
   config::{LauncherConfig, SupportedGame},
   util::{
-    file::{create_dir, delete_dir, overwrite_dir},
+    cancellation,
+    file::{create_dir, delete_dir, overwrite_dir, replace_dir, staging_dir},
     zip::{check_if_zip_contains_top_level_entry, extract_zip_file},
   },
 };
@@ -146,6 +147,7 @@ pub async fn extract_new_texture_pack(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   game_name: SupportedGame,
   zip_path: PathBuf,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
   let install_dir = {
     let config_lock = config.lock().await;
@@ -161,15 +163,26 @@ pub async fn extract_new_texture_pack(
   let expected_top_level_dir = format!("custom_assets/{game_name}/texture_replacements");
   check_if_zip_contains_top_level_entry(&zip_path, &expected_top_level_dir)?;
 
-  // The name of the zip becomes the folder, if one already exists it will be deleted!
-  let destination_dir = &install_dir
+  // The name of the zip becomes the folder, if one already exists it will be replaced!
+  let destination_dir = install_dir
     .join("features")
     .join(game_name.to_string())
     .join("texture-packs")
     .join(&texture_pack_name);
-  // TODO - delete it
-  create_dir(destination_dir)?;
-  extract_zip_file(&zip_path, destination_dir, false).context("Unable to extract texture pack")?;
+  let operation_id =
+    operation_id.unwrap_or_else(|| format!("texture-pack:{game_name}/{texture_pack_name}"));
+  let _operation = cancellation::register(&operation_id);
+  // The existing pack stays untouched until the new one has been extracted in full
+  let staging_dir = staging_dir(&install_dir, ".texture-pack-")?;
+  let staged_pack_dir = staging_dir.path().join("contents");
+  extract_zip_file(&zip_path, &staged_pack_dir, false, Some(&operation_id))
+    .context("Unable to extract texture pack")?;
+  if !staged_pack_dir.join(&expected_top_level_dir).is_dir() {
+    return Err(CommandError::GameFeatures(format!(
+      "Texture pack has no '{expected_top_level_dir}' folder"
+    )));
+  }
+  replace_dir(&staged_pack_dir, &destination_dir)?;
   Ok(())
 }
 
@@ -1,15 +1,87 @@
 use anyhow::Result;
+use serde::Serialize;
 use std::{collections::HashMap, path::Path};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tracing::instrument;
+use ts_rs::TS;
 use walkdir::WalkDir;
 
 use crate::{
-  config::{LauncherConfig, SupportedGame},
-  util::game_milestones::{GameTaskStatus, MilestoneCriteria, get_jak1_milestones},
+  config::{LauncherConfig, OutdatedGame, SupportedGame},
+  util::{
+    cancellation,
+    game_milestones::{GameTaskStatus, MilestoneCriteria, get_jak1_milestones},
+  },
 };
 
-use super::CommandError;
+use super::{CommandError, binaries::rebuild_game};
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct OutdatedGamesUpdate {
+  pub updated: Vec<SupportedGame>,
+  pub failed: HashMap<SupportedGame, String>,
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn list_outdated_games(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<OutdatedGame>, CommandError> {
+  Ok(config.lock().await.outdated_games())
+}
+
+#[instrument(skip(config, app_handle))]
+#[tauri::command]
+pub async fn update_outdated_games(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  app_handle: tauri::AppHandle,
+) -> Result<OutdatedGamesUpdate, CommandError> {
+  Ok(rebuild_outdated_games(&config, &app_handle).await)
+}
+
+/// Rebuilds every outdated game, one at a time.  A game that fails to rebuild doesn't stop the
+/// others from being attempted.
+pub async fn rebuild_outdated_games(
+  config: &tokio::sync::Mutex<LauncherConfig>,
+  app_handle: &tauri::AppHandle,
+) -> OutdatedGamesUpdate {
+  let outdated = config.lock().await.outdated_games();
+  let mut result = OutdatedGamesUpdate::default();
+
+  for OutdatedGame { game, .. } in outdated {
+    let operation_id = format!("rebuild:{game}");
+    let _operation = cancellation::register(&operation_id);
+    // Only locked to read the config and to record the result, a rebuild takes minutes
+    let config_info = config.lock().await.common_prelude_for_game(game);
+    let rebuilt = match config_info {
+      Ok(config_info) => rebuild_game(&config_info, app_handle, game, &operation_id).await,
+      Err(err) => Err(err),
+    };
+    let rebuilt = match rebuilt {
+      Ok(()) => config
+        .lock()
+        .await
+        .update(|config| {
+          config.set_game_installed(game, true);
+          Ok(())
+        })
+        .map_err(|err| format!("Unable to persist game installation status {err}")),
+      Err(err) => Err(err.to_string()),
+    };
+    match rebuilt {
+      Ok(()) => result.updated.push(game),
+      Err(err) => {
+        tracing::error!("Unable to update {game}: {err}");
+        result.failed.insert(game, err);
+      }
+    }
+  }
+
+  let _ = app_handle.emit("outdated_games_updated", result.clone());
+  result
+}
 
 #[instrument(skip(config))]
 #[tauri::command]
@@ -37,7 +109,10 @@ pub async fn uninstall_game(
   config
     .lock()
     .await
-    .set_game_installed(game_name, false)
+    .update(|config| {
+      config.set_game_installed(game_name, false);
+      Ok(())
+    })
     .map_err(|err| {
       CommandError::GameManagement(format!("Unable to persist game installation status {err}"))
     })?;
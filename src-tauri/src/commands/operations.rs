@@ -0,0 +1,12 @@
+use tracing::instrument;
+
+use crate::util::cancellation;
+
+use super::CommandError;
+
+/// Returns false if the operation isn't running (anymore)
+#[instrument]
+#[tauri::command]
+pub async fn cancel_operation(operation_id: String) -> Result<bool, CommandError> {
+  Ok(cancellation::cancel(&operation_id))
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use tracing::instrument;
+
+use crate::config::{InstallProfileInfo, LauncherConfig};
+
+use super::CommandError;
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn list_install_profiles(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<Vec<InstallProfileInfo>, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.list_profiles())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn create_install_profile(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  name: String,
+  installation_dir: Option<PathBuf>,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .update(|config| config.create_profile(name, installation_dir))
+    .map_err(|err| {
+      tracing::error!("Unable to create install profile: {:?}", err);
+      CommandError::Configuration(format!("Unable to create install profile: {err}"))
+    })?;
+  Ok(())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn switch_install_profile(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  name: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .update(|config| config.switch_profile(&name))
+    .map_err(|err| {
+      tracing::error!("Unable to switch install profile: {:?}", err);
+      CommandError::Configuration(format!("Unable to switch install profile: {err}"))
+    })?;
+  Ok(())
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn delete_install_profile(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  name: String,
+) -> Result<(), CommandError> {
+  let mut config_lock = config.lock().await;
+  config_lock
+    .update(|config| config.delete_profile(&name))
+    .map_err(|err| {
+      tracing::error!("Unable to delete install profile: {:?}", err);
+      CommandError::Configuration(format!("Unable to delete install profile: {err}"))
+    })?;
+  Ok(())
+}
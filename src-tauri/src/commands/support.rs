@@ -164,12 +164,8 @@ fn dump_per_game_info(
     &format!("Game Logs and ISO Info/{game}/buildinfo.json"),
   )?;
 
-  if let Some(active_version) = &config_lock.active_version {
-    let version_data_dir = install_path
-      .join("versions")
-      .join("official")
-      .join(active_version)
-      .join("data");
+  if let Ok(version_dir) = config_lock.active_version_dir() {
+    let version_data_dir = version_dir.join("data");
 
     let game_info = package.game_info.get_game_info(game_name);
     game_info.release_integrity.decompiler_folder_state =
@@ -287,8 +283,8 @@ pub async fn generate_support_package(
   package.launcher_version = app_handle.package_info().version.to_string();
   package.active_tooling_version = config_lock
     .active_version
-    .clone()
-    .unwrap_or("unset".to_string());
+    .as_ref()
+    .map_or("unset".to_string(), ToString::to_string);
   package.install_dir = install_path.to_string_lossy().to_string();
   package.kernel_uptime = format!(
     "{}d {:02}h {:02}m",
@@ -297,37 +293,17 @@ pub async fn generate_support_package(
     (uptime / 60) % 60,
   );
 
-  if let Some(active_version) = &config_lock.active_version {
+  if let Ok(version_dir) = config_lock.active_version_dir() {
     #[cfg(windows)]
     {
-      package.extractor_binary_exists = install_path
-        .join("versions")
-        .join("official")
-        .join(active_version)
-        .join("extractor.exe")
-        .exists();
-      package.game_binary_exists = install_path
-        .join("versions")
-        .join("official")
-        .join(active_version)
-        .join("gk.exe")
-        .exists();
+      package.extractor_binary_exists = version_dir.join("extractor.exe").exists();
+      package.game_binary_exists = version_dir.join("gk.exe").exists();
     }
 
     #[cfg(not(windows))]
     {
-      package.extractor_binary_exists = install_path
-        .join("versions")
-        .join("official")
-        .join(active_version)
-        .join("extractor")
-        .exists();
-      package.game_binary_exists = install_path
-        .join("versions")
-        .join("official")
-        .join(active_version)
-        .join("gk")
-        .exists();
+      package.extractor_binary_exists = version_dir.join("extractor").exists();
+      package.game_binary_exists = version_dir.join("gk").exists();
     }
   }
 
@@ -1,6 +1,7 @@
 use crate::config::LauncherConfig;
 use crate::config::SupportedGame;
 use anyhow::anyhow;
+use std::path::Path;
 use sysinfo::Disks;
 use tracing::instrument;
 use tracing::warn;
@@ -27,19 +28,20 @@ pub async fn is_diskspace_requirement_met(
 
   let minimum_required_drive_space = game_name.required_diskspace();
 
+  available_disk_space(&install_dir)
+    .map(|available_space| available_space >= minimum_required_drive_space)
+    .map_err(|e| anyhow!("Failed to find relevant drive to check for space: {e}"))
+    .map_err(Into::into)
+}
+
+/// Free space on the disk that `path` lives on
+pub fn available_disk_space(path: &Path) -> anyhow::Result<u64> {
   Disks::new_with_refreshed_list()
     .iter()
-    .filter(|d| install_dir.starts_with(d.mount_point()))
+    .filter(|d| path.starts_with(d.mount_point()))
     .max_by_key(|d| d.mount_point().as_os_str().len())
-    .map(|disk| disk.available_space() >= minimum_required_drive_space)
-    .ok_or_else(|| {
-      anyhow!(
-        "No disk found for install directory {}",
-        install_dir.display()
-      )
-    })
-    .map_err(|e| anyhow!("Failed to find relevant drive to check for space: {e}"))
-    .map_err(Into::into)
+    .map(|disk| disk.available_space())
+    .ok_or_else(|| anyhow!("No disk found for install directory {}", path.display()))
 }
 
 #[cfg(target_os = "windows")]
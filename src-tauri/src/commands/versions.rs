@@ -1,8 +1,17 @@
+use std::path::{Component, Path, PathBuf};
+
 use tracing::instrument;
 
 use crate::{
-  config::LauncherConfig,
-  util::{file::delete_dir, network::download_file, tar::extract_and_delete_archive},
+  config::{LauncherConfig, RetentionPlan, VersionChannel},
+  releases::{ReleaseIndex, ReleaseInfo},
+  util::{
+    cancellation,
+    file::{create_dir, replace_dir, sha256_file},
+    network::{download_checksum, download_file},
+    tar::{extract_and_delete_archive, extract_archive},
+    tooling::{self, ToolingProbe},
+  },
 };
 
 use super::CommandError;
@@ -11,27 +20,76 @@ use super::CommandError;
 #[tauri::command]
 pub async fn list_downloaded_versions(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
-  version_folder: String,
+  channel: VersionChannel,
 ) -> Result<Vec<String>, CommandError> {
   let config = config.lock().await;
-  Ok(config.list_downloaded_versions(&version_folder)?)
+  Ok(config.list_downloaded_versions(channel)?)
+}
+
+/// Official releases, newest first.  Cached for a few minutes unless `refresh` is set.
+#[instrument(skip(config, release_index))]
+#[tauri::command]
+pub async fn list_available_versions(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  release_index: tauri::State<'_, tokio::sync::Mutex<ReleaseIndex>>,
+  refresh: bool,
+) -> Result<Vec<ReleaseInfo>, CommandError> {
+  let endpoint = config.lock().await.release_index_url().to_owned();
+  let mut index_lock = release_index.lock().await;
+  Ok(index_lock.releases(&endpoint, refresh).await?.to_vec())
+}
+
+#[instrument(skip(config, release_index))]
+#[tauri::command]
+pub async fn get_latest_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  release_index: tauri::State<'_, tokio::sync::Mutex<ReleaseIndex>>,
+  refresh: bool,
+) -> Result<Option<ReleaseInfo>, CommandError> {
+  let endpoint = config.lock().await.release_index_url().to_owned();
+  let mut index_lock = release_index.lock().await;
+  Ok(index_lock.latest_release(&endpoint, refresh).await?)
 }
 
+// Deletes the archive if it doesn't match, so a bad download is never extracted or reused
+fn verify_checksum(archive_path: &Path, expected: &str) -> Result<(), CommandError> {
+  let actual = sha256_file(archive_path)?;
+  if actual.eq_ignore_ascii_case(expected.trim()) {
+    tracing::info!("Verified checksum of {}", archive_path.display());
+    return Ok(());
+  }
+
+  tracing::error!(
+    "Checksum mismatch for {}, expected {expected} but got {actual}",
+    archive_path.display()
+  );
+  let _ = std::fs::remove_file(archive_path);
+  Err(CommandError::ChecksumMismatch {
+    file: archive_path.display().to_string(),
+    expected: expected.trim().to_lowercase(),
+    actual,
+  })
+}
+
+/// If no `checksum` (hex encoded SHA-256) is provided, the one published alongside the release is
+/// used.  Releases without one are installed unverified.
 #[instrument(skip(config))]
 #[tauri::command]
 pub async fn download_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
   version: String,
-  version_folder: String,
+  channel: VersionChannel,
   url: String,
+  checksum: Option<String>,
+  operation_id: Option<String>,
 ) -> Result<(), CommandError> {
-  let versions_dir = config
-    .lock()
-    .await
-    .install_dir()?
-    .join("versions")
-    .join(&version_folder);
-  let dest_dir = versions_dir.join(&version);
+  let operation_id = operation_id.unwrap_or_else(|| format!("version:{channel}/{version}"));
+  let _operation = cancellation::register(&operation_id);
+  let dest_dir = config.lock().await.version_dir(channel, &version)?;
+  let versions_dir = dest_dir
+    .parent()
+    .map(Path::to_path_buf)
+    .ok_or_else(|| anyhow::anyhow!("Invalid version directory: {}", dest_dir.display()))?;
 
   #[cfg(windows)]
   let download_path = versions_dir.join(format!("{version}.zip"));
@@ -39,20 +97,191 @@ pub async fn download_version(
   #[cfg(unix)]
   let download_path = versions_dir.join(format!("{version}.tar.gz"));
 
-  delete_dir(&dest_dir)?;
-  download_file(&url, &download_path).await?;
-  extract_and_delete_archive(&download_path, &dest_dir, true)?;
+  download_file(&url, &download_path, Some(&operation_id)).await?;
+
+  let checksum = match checksum {
+    Some(checksum) => Some(checksum),
+    None => download_checksum(&url).await,
+  };
+  match checksum {
+    Some(checksum) => verify_checksum(&download_path, &checksum)?,
+    None => tracing::warn!("No checksum available for {url}, skipping verification"),
+  }
+
+  // A version that's already downloaded stays untouched until the new copy is extracted in full,
+  // the hidden staging folder isn't listed as a version in the meantime
+  let staging_dir = tempfile::Builder::new()
+    .prefix(".download-")
+    .tempdir_in(&versions_dir)?;
+  let staged_version_dir = staging_dir.path().join("contents");
+  extract_and_delete_archive(
+    &download_path,
+    &staged_version_dir,
+    true,
+    Some(&operation_id),
+  )?;
+  replace_dir(&staged_version_dir, &dest_dir)?;
+
+  let mut config_lock = config.lock().await;
+  if channel == VersionChannel::Official && config_lock.delete_previous_versions {
+    // The download itself succeeded, failing to clean up shouldn't fail it
+    if let Err(err) = config_lock.apply_version_retention() {
+      tracing::warn!("Unable to remove old versions: {err:#}");
+    }
+  }
+  Ok(())
+}
+
+// Archives usually wrap the build in a folder of its own
+fn find_build_root(dir: &Path) -> PathBuf {
+  let entries: Vec<_> = std::fs::read_dir(dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entry| entry.path())
+    .collect();
+  match entries.as_slice() {
+    [only] if only.is_dir() && !tooling::missing_binaries(dir).is_empty() => only.clone(),
+    _ => dir.to_path_buf(),
+  }
+}
+
+fn validate_build(dir: &Path) -> Result<(), CommandError> {
+  let missing = tooling::missing_binaries(dir);
+  if !missing.is_empty() {
+    return Err(CommandError::Configuration(format!(
+      "{} is missing {}",
+      dir.display(),
+      missing.join(", ")
+    )));
+  }
+  if !dir.join("data").is_dir() {
+    return Err(CommandError::Configuration(format!(
+      "{} has no data directory",
+      dir.display()
+    )));
+  }
   Ok(())
 }
 
+/// Registers a locally built version, either its build directory or an archive of it, in the
+/// `local` channel.  It's named after the version the binaries report unless a `name` is given.
+/// Importing under an existing name replaces that version.
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn import_local_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  source: PathBuf,
+  name: Option<String>,
+) -> Result<String, CommandError> {
+  let local_dir = config
+    .lock()
+    .await
+    .install_dir()?
+    .join("versions")
+    .join(VersionChannel::Local.to_string());
+  create_dir(&local_dir)?;
+
+  // Everything is put together next to where it ends up, so it can be moved into place as a whole
+  let staging_dir = tempfile::Builder::new()
+    .prefix(".import-")
+    .tempdir_in(&local_dir)?;
+  let build_dir = if source.is_dir() {
+    validate_build(&source)?;
+    let options = fs_extra::dir::CopyOptions::new().content_only(true);
+    fs_extra::dir::copy(&source, staging_dir.path(), &options)
+      .map_err(|err| anyhow::anyhow!("Unable to copy {}: {err}", source.display()))?;
+    staging_dir.path().to_path_buf()
+  } else {
+    extract_archive(&source, staging_dir.path(), false, None)?;
+    let build_dir = find_build_root(staging_dir.path());
+    validate_build(&build_dir)?;
+    build_dir
+  };
+
+  let probe = tooling::probe(&build_dir)
+    .await
+    .map_err(|err| CommandError::BinaryExecution(format!("{err:#}")))?;
+  let name = match name.map(|name| name.trim().to_owned()) {
+    Some(name) if !name.is_empty() => name,
+    _ => match &probe.version {
+      Some(version) => format!("v{version}"),
+      None => {
+        return Err(CommandError::Configuration(
+          "Unable to determine the version of the build, please name it".to_owned(),
+        ));
+      }
+    },
+  };
+  let mut components = Path::new(&name).components();
+  if !matches!(components.next(), Some(Component::Normal(_)))
+    || components.next().is_some()
+    || name.starts_with('.')
+  {
+    return Err(CommandError::Configuration(format!(
+      "'{name}' can't be used as a version name"
+    )));
+  }
+  probe.save(&build_dir)?;
+  match &probe.version {
+    Some(version) => tracing::info!(
+      "Importing {} (reports {version}) as {name}",
+      source.display()
+    ),
+    None => tracing::info!("Importing {} as {name}", source.display()),
+  }
+
+  let config_lock = config.lock().await;
+  let dest_dir = config_lock.version_dir(VersionChannel::Local, &name)?;
+  replace_dir(&build_dir, &dest_dir)?;
+  if config_lock.is_active_version(VersionChannel::Local, &name) {
+    config_lock.copy_active_binaries()?;
+  }
+  Ok(name)
+}
+
+/// Runs the version's binaries to check they work, recording what they report
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn probe_tooling_version(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  channel: VersionChannel,
+  version: String,
+) -> Result<ToolingProbe, CommandError> {
+  let version_dir = config.lock().await.version_dir(channel, &version)?;
+  tooling::probe_and_record(&version_dir)
+    .await
+    .map_err(|err| CommandError::BinaryExecution(format!("{err:#}")))
+}
+
 #[instrument(skip(config))]
 #[tauri::command]
 pub async fn remove_version(
   config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+  channel: VersionChannel,
   version: String,
 ) -> Result<(), CommandError> {
   let mut config_lock = config.lock().await;
-  Ok(config_lock.remove_version(&version)?)
+  Ok(config_lock.remove_version(channel, &version)?)
+}
+
+/// Lists the versions that cleaning up old versions would remove, without removing them
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn preview_version_retention(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<RetentionPlan, CommandError> {
+  let config_lock = config.lock().await;
+  Ok(config_lock.version_retention_plan()?)
+}
+
+#[instrument(skip(config))]
+#[tauri::command]
+pub async fn apply_version_retention(
+  config: tauri::State<'_, tokio::sync::Mutex<LauncherConfig>>,
+) -> Result<RetentionPlan, CommandError> {
+  let mut config_lock = config.lock().await;
+  Ok(config_lock.apply_version_retention()?)
 }
 
 #[instrument(skip(config))]
@@ -4,25 +4,41 @@
 //
 // Read the config, if it's not there, we'll generate a default
 //
-// Using something like config-rs or figment might be nice to provide a dev override
-// but this is also as simple as just editing the file so for now, not needed
+// A few settings can be overridden for the current session through the environment or
+// command-line flags, see `config/overrides.rs`
 //
 // serde does not support defaultLiterals yet - https://github.com/serde-rs/serde/issues/368
 
+mod backups;
+mod doctor;
+mod migrations;
+mod overrides;
+mod retention;
+mod watcher;
+
+pub use backups::SettingsBackup;
+pub use doctor::InstallProblem;
+pub use overrides::ConfigOverrides;
+pub use retention::RetentionPlan;
+pub use watcher::SettingsWatcher;
+
 use crate::util::emit_config_saved;
-use crate::util::file::create_dir;
+use crate::util::file::write_file_atomic;
 use crate::{commands::CommandError, util::file::delete_dir};
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
-use strum_macros::{Display, EnumIter};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
 use ts_rs::TS;
 
+use crate::util::activation;
 use crate::util::file::touch_file;
+use crate::util::tooling::{self, ToolingProbe};
 
 #[derive(
   Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize, Display, EnumIter, TS,
@@ -47,11 +63,40 @@ impl SupportedGame {
   }
 }
 
+/// Where a tooling version comes from, each channel has its own folder under `versions/`
+#[derive(
+  Debug,
+  Default,
+  Eq,
+  PartialEq,
+  Hash,
+  Clone,
+  Copy,
+  Serialize,
+  Deserialize,
+  Display,
+  EnumIter,
+  EnumString,
+  TS,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+#[ts(export)]
+pub enum VersionChannel {
+  #[default]
+  Official,
+  Unofficial,
+  Devel,
+  Local,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, TS)]
 #[serde(rename_all = "camelCase", default)]
 pub struct GameConfig {
   pub is_installed: bool,
-  pub version: Option<String>,
+  pub version: Option<ChannelVersion>,
+  // Tooling version this game always uses, regardless of the active version
+  pub pinned_version: Option<ChannelVersion>,
   pub texture_packs: Vec<String>,
   pub seconds_played: u64,
   #[serde(rename = "mods")]
@@ -86,7 +131,7 @@ impl GameConfig {
     self
   }
 
-  pub fn set_version(&mut self, version: Option<String>) -> &mut Self {
+  pub fn set_version(&mut self, version: Option<ChannelVersion>) -> &mut Self {
     self.version = version;
     self
   }
@@ -97,6 +142,86 @@ impl GameConfig {
   }
 }
 
+/// Everything tied to a single installation directory
+///
+/// The active profile always lives in the top-level fields of `LauncherConfig`, so everything that
+/// reads the config (ie. `common_prelude`) transparently works against it.  Switching profiles
+/// swaps these fields out.
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InstallProfile {
+  pub installation_dir: Option<PathBuf>,
+  pub active_version: Option<ChannelVersion>,
+  pub games: HashMap<SupportedGame, GameConfig>,
+}
+
+impl Default for InstallProfile {
+  fn default() -> Self {
+    Self {
+      installation_dir: None,
+      active_version: None,
+      games: default_games(),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct InstallProfileInfo {
+  pub name: String,
+  pub installation_dir: Option<PathBuf>,
+  pub active_version: Option<ChannelVersion>,
+  pub is_active: bool,
+}
+
+/// A tooling version along with the channel it was taken from
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ChannelVersion {
+  pub channel: VersionChannel,
+  pub version: String,
+}
+
+impl ChannelVersion {
+  pub fn new(channel: VersionChannel, version: String) -> Self {
+    Self { channel, version }
+  }
+
+  pub fn is(&self, channel: VersionChannel, version: &str) -> bool {
+    self.channel == channel && self.version == version
+  }
+}
+
+// "<channel>/<version>", the same as where the version lives under `versions/`
+impl std::fmt::Display for ChannelVersion {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.channel, self.version)
+  }
+}
+
+impl std::str::FromStr for ChannelVersion {
+  type Err = anyhow::Error;
+
+  fn from_str(label: &str) -> Result<Self> {
+    let (channel, version) = label
+      .split_once('/')
+      .with_context(|| format!("'{label}' is not in the form <channel>/<version>"))?;
+    Ok(Self::new(channel.parse()?, version.to_owned()))
+  }
+}
+
+/// An installed game that was built with a different tooling version than it now runs with
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct OutdatedGame {
+  pub game: SupportedGame,
+  pub installed_version: Option<ChannelVersion>,
+  pub expected_version: ChannelVersion,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, TS)]
 #[serde(rename_all = "camelCase", default)]
 pub struct Requirements {
@@ -154,13 +279,23 @@ pub struct LauncherConfig {
   #[serde(skip)]
   #[ts(skip)]
   settings_path: PathBuf,
+  #[serde(skip)]
+  #[ts(skip)]
+  overrides: overrides::OverrideLayer,
+  // Only ever reported to the frontend, never persisted
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub overridden_fields: Vec<String>,
   #[serde(default = "default_version")]
   pub version: String,
   pub requirements: Requirements,
   #[serde(default = "default_games")]
   pub games: HashMap<SupportedGame, GameConfig>,
   pub installation_dir: Option<PathBuf>,
-  pub active_version: Option<String>,
+  pub active_version: Option<ChannelVersion>,
+  #[serde(default = "default_profile_name")]
+  pub active_profile: String,
+  // Every profile except the active one, which is stored in the fields above
+  pub inactive_profiles: BTreeMap<String, InstallProfile>,
   pub locale: String,
   pub mod_sources: Vec<String>,
   pub decompiler_settings: DecompilerSettings,
@@ -168,19 +303,32 @@ pub struct LauncherConfig {
   pub proceed_after_successful_operation: bool,
   pub auto_update_games: bool,
   pub delete_previous_versions: bool,
+  // How many of the newest versions survive when old versions are cleaned up
+  #[serde(default = "default_versions_to_keep")]
+  pub versions_to_keep: u32,
+  // Where to look for tooling releases, the official GitHub releases if unset
+  pub release_index_url: Option<String>,
   pub ui: UISettings,
 }
 
 pub struct CommonConfigData {
   pub install_path: PathBuf,
   pub active_version: String,
-  #[allow(dead_code)]
+  pub channel: VersionChannel,
+  // Where the version in use was downloaded (or built) to
+  pub version_dir: PathBuf,
   pub tooling_version: Version,
+  pub bin_dir: PathBuf,
 }
 
 impl CommonConfigData {
+  /// Identifies the version in use, ie. `official/v0.2.14`
+  pub fn version_label(&self) -> String {
+    format!("{}/{}", self.channel, self.active_version)
+  }
+
   pub fn get_exec_location(&self, executable_name: &str) -> ExecutableLocation {
-    let exec_dir = self.install_path.join("active").join("bin");
+    let exec_dir = self.bin_dir.clone();
 
     let mut exec_path: PathBuf = exec_dir.join(executable_name);
     if cfg!(windows) {
@@ -200,7 +348,38 @@ pub struct ExecutableLocation {
 }
 
 fn default_version() -> String {
-  "3.0".to_owned()
+  migrations::CURRENT_VERSION.to_owned()
+}
+
+// Check our permissions on the folder by touching a file (and deleting it)
+fn ensure_dir_writable(path: &Path) -> Result<()> {
+  let test_file = path.join(".perm-test.tmp");
+  touch_file(&test_file).context("Provided installation folder could not be written to.")?;
+  let _ = fs::remove_file(&test_file);
+  Ok(())
+}
+
+fn default_profile_name() -> String {
+  "default".to_owned()
+}
+
+fn default_versions_to_keep() -> u32 {
+  1
+}
+
+fn parse_version_name(version: &str) -> Option<Version> {
+  Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+// Prefers what the binaries reported when they were probed, the folder name is just a name
+fn tooling_version(version_dir: &Path, version: &str) -> Version {
+  ToolingProbe::load(version_dir)
+    .and_then(|probe| probe.version())
+    .or_else(|| parse_version_name(version))
+    .unwrap_or_else(|| {
+      tracing::warn!("Unable to determine the tooling version of {version}, assuming 0.1.35");
+      Version::new(0, 1, 35)
+    })
 }
 
 fn default_games() -> HashMap<SupportedGame, GameConfig> {
@@ -225,11 +404,15 @@ impl LauncherConfig {
   fn default_with_path(_settings_path: PathBuf) -> Self {
     Self {
       settings_path: _settings_path,
+      overrides: overrides::OverrideLayer::default(),
+      overridden_fields: Vec::new(),
       version: default_version(),
       requirements: Requirements::default(),
       games: default_games(),
       installation_dir: None,
       active_version: None,
+      active_profile: default_profile_name(),
+      inactive_profiles: BTreeMap::new(),
       locale: "en-US".to_owned(),
       mod_sources: Vec::new(),
       decompiler_settings: DecompilerSettings::default(),
@@ -237,14 +420,17 @@ impl LauncherConfig {
       proceed_after_successful_operation: true,
       auto_update_games: false,
       delete_previous_versions: false,
+      versions_to_keep: default_versions_to_keep(),
+      release_index_url: None,
       ui: UISettings::default(),
     }
   }
 
   fn backup(settings_path: &Path) {
-    tracing::warn!("Creating a backup copy of existing settings.");
-    let dest = settings_path.with_file_name("settings.backup.json");
-    let _ = fs::copy(settings_path.to_path_buf(), dest);
+    tracing::info!("Creating a backup copy of existing settings.");
+    if let Err(err) = backups::create_backup(settings_path) {
+      tracing::error!("Unable to back up settings: {err:#}");
+    }
   }
 
   pub fn get_supported_game_config_mut(&mut self, game_name: SupportedGame) -> &mut GameConfig {
@@ -255,50 +441,304 @@ impl LauncherConfig {
     let settings_path = config_dir.join("settings.json");
     tracing::info!("Loading configuration at path: {}", settings_path.display());
 
-    let file = std::fs::File::open(&settings_path);
-    let mut config: LauncherConfig = match file {
-      Ok(file) => serde_json::from_reader(file).unwrap_or_else(|_| {
-        tracing::warn!("Failed to load or parse settings file, using defaults");
-        LauncherConfig::backup(&settings_path);
-        LauncherConfig::default_with_path(settings_path.clone())
-      }),
+    let contents = std::fs::read_to_string(&settings_path);
+    let mut config: LauncherConfig = match contents {
+      Ok(contents) => match LauncherConfig::parse_and_migrate(&settings_path, &contents) {
+        Ok(config) => {
+          // Keep a history of known-good settings, one snapshot per launch at most
+          LauncherConfig::backup(&settings_path);
+          config
+        }
+        Err(err) => {
+          tracing::warn!("Failed to load or parse settings file, using defaults: {err:#}");
+          LauncherConfig::backup(&settings_path);
+          LauncherConfig::default_with_path(settings_path.clone())
+        }
+      },
       Err(_) => LauncherConfig::default_with_path(settings_path.clone()),
     };
 
     config.settings_path = settings_path;
+    config.apply_overrides(ConfigOverrides::from_env());
     config
   }
 
+  fn parse_and_migrate(settings_path: &Path, contents: &str) -> Result<LauncherConfig> {
+    let mut raw: serde_json::Value = serde_json::from_str(contents)?;
+    let migrated_from = migrations::migrate(&mut raw)?;
+
+    let mut config: LauncherConfig = serde_json::from_value(raw)?;
+    config.settings_path = settings_path.to_path_buf();
+    config.drop_orphaned_active_versions();
+
+    // Persist the upgraded layout right away, keeping a copy of the original around
+    if let Some(original_version) = migrated_from {
+      tracing::info!(
+        "Settings migrated from version {original_version} to {}",
+        config.version
+      );
+      LauncherConfig::backup(settings_path);
+      config.write_config()?;
+    }
+    Ok(config)
+  }
+
+  // Older launchers could end up with an active version but no installation directory, which
+  // there is nothing to activate the version in.  It's cleared rather than refused, everything
+  // else in the file is still good.
+  fn drop_orphaned_active_versions(&mut self) {
+    if self.installation_dir.is_none() && self.active_version.is_some() {
+      tracing::warn!("Clearing the active version, no installation directory is set");
+      self.clear_active_version();
+    }
+    for (name, profile) in &mut self.inactive_profiles {
+      if profile.installation_dir.is_none() && profile.active_version.is_some() {
+        tracing::warn!(
+          "Clearing the active version of profile '{name}', it has no installation directory"
+        );
+        profile.active_version = None;
+      }
+    }
+  }
+
+  /// Layers session-only overrides on top of the loaded settings
+  pub fn apply_overrides(&mut self, overrides: ConfigOverrides) {
+    self.overridden_fields = overrides.fields();
+    if overrides.is_empty() {
+      return;
+    }
+    tracing::info!("Overriding settings: {}", self.overridden_fields.join(", "));
+    self.overrides = overrides::OverrideLayer::apply(overrides, self);
+  }
+
+  // What gets written to the settings file, without any overrides
+  fn persisted_contents(&self) -> Result<Vec<u8>> {
+    if self.overrides.overrides().is_empty() {
+      return Ok(serde_json::to_vec_pretty(&self)?);
+    }
+    let mut persisted = self.clone();
+    self.overrides.restore(&mut persisted);
+    persisted.overridden_fields.clear();
+    Ok(serde_json::to_vec_pretty(&persisted)?)
+  }
+
+  fn write_config(&self) -> Result<()> {
+    write_file_atomic(&self.settings_path, &self.persisted_contents()?)
+  }
+
+  pub fn watch_for_changes(&self, app_handle: tauri::AppHandle) -> Result<SettingsWatcher> {
+    watcher::watch(app_handle, &self.settings_path)
+  }
+
+  /// Replaces the config with `contents`, as read from the settings file after an external edit.
+  ///
+  /// Returns false if the contents already match the config (ie. it was our own write)
+  pub fn reload(&mut self, contents: &str) -> Result<bool> {
+    let on_disk: serde_json::Value =
+      serde_json::from_str(contents).context("Settings file is not valid json")?;
+    let current: serde_json::Value = serde_json::from_slice(&self.persisted_contents()?)?;
+    if on_disk == current {
+      return Ok(false);
+    }
+
+    let mut reloaded = Self::parse_and_migrate(&self.settings_path, contents)?;
+    reloaded.apply_overrides(self.overrides.overrides().clone());
+    reloaded.validate()?;
+    *self = reloaded;
+    emit_config_saved()?;
+    Ok(true)
+  }
+
   pub fn save_config(&self) -> Result<()> {
-    let settings_path = &self.settings_path;
+    self.write_config()?;
+    emit_config_saved()?;
+    Ok(())
+  }
+
+  pub fn diagnose_install(&self) -> Result<Vec<InstallProblem>> {
+    doctor::diagnose(self)
+  }
 
-    // Ensure the directory exists
-    create_dir(settings_path.parent().unwrap())?;
-    let file = fs::File::create(settings_path)?;
-    serde_json::to_writer_pretty(file, &self)?;
+  /// Applies the suggested fix for every problem found, returning the problems that were fixed
+  pub fn repair_install(&mut self) -> Result<Vec<InstallProblem>> {
+    let problems = doctor::diagnose(self)?;
+    if !problems.is_empty() {
+      let fixes: Vec<_> = problems.iter().map(|p| p.suggested_fix.clone()).collect();
+      doctor::apply_fixes(self, &fixes)?;
+    }
+    Ok(problems)
+  }
+
+  pub fn list_backups(&self) -> Result<Vec<SettingsBackup>> {
+    backups::list_backups(&self.settings_path)
+  }
+
+  pub fn restore_backup(&mut self, file_name: &str) -> Result<()> {
+    let contents = backups::read_backup(&self.settings_path, file_name)?;
+    let restored = Self::parse_and_migrate(&self.settings_path, &contents)
+      .with_context(|| format!("Settings backup {file_name} is not valid"))?;
+
+    // Restoring is itself undoable
+    Self::backup(&self.settings_path);
+    tracing::info!("Restoring settings from backup {file_name}");
+    let overrides = self.overrides.overrides().clone();
+    *self = restored;
+    self.apply_overrides(overrides);
+    self.save_config()?;
+    Ok(())
+  }
+
+  /// Applies a batch of changes to the config, validates the result and then persists it once.
+  ///
+  /// If `apply`, the validation or the write fails, the config is left as it was, both in memory
+  /// and in the settings file.  That only covers the config: anything `apply` does to the
+  /// filesystem isn't undone, so such work belongs after `update` has returned.
+  pub fn update<T>(&mut self, apply: impl FnOnce(&mut LauncherConfig) -> Result<T>) -> Result<T> {
+    let mut updated = self.clone();
+    let result = apply(&mut updated)?;
+    updated.validate()?;
+    updated.write_config()?;
+    *self = updated;
     emit_config_saved()?;
+    Ok(result)
+  }
+
+  fn validate(&self) -> Result<()> {
+    if let Some(dir) = &self.installation_dir
+      && !dir.is_absolute()
+    {
+      bail!(
+        "Installation directory must be an absolute path: {}",
+        dir.display()
+      );
+    }
+    if self.locale.is_empty() {
+      bail!("Locale can't be empty");
+    }
+    if self
+      .mod_sources
+      .iter()
+      .any(|source| source.trim().is_empty())
+    {
+      bail!("Mod sources can't be empty");
+    }
+    if self.active_profile.trim().is_empty() {
+      bail!("Install profile names can't be empty");
+    }
+    if self.inactive_profiles.contains_key(&self.active_profile) {
+      bail!("Install profile '{}' is defined twice", self.active_profile);
+    }
     Ok(())
   }
 
-  pub fn reset_to_defaults(&mut self) -> Result<()> {
+  pub fn reset_to_defaults(&mut self) {
+    // Reset what's in the file, the overrides still apply on top of it
+    let overrides = std::mem::take(&mut self.overrides);
+    overrides.restore(self);
     let original_installation_dir = self.installation_dir.clone();
+    let original_active_profile = std::mem::take(&mut self.active_profile);
+    let original_inactive_profiles = std::mem::take(&mut self.inactive_profiles);
     *self = Self::default_with_path(self.settings_path.clone());
     self.installation_dir = original_installation_dir;
-    Self::save_config(self)?;
+    self.active_profile = original_active_profile;
+    self.inactive_profiles = original_inactive_profiles;
+    self.apply_overrides(overrides.overrides().clone());
+  }
+
+  pub fn list_profiles(&self) -> Vec<InstallProfileInfo> {
+    let active = InstallProfileInfo {
+      name: self.active_profile.clone(),
+      installation_dir: self.installation_dir.clone(),
+      active_version: self.active_version.clone(),
+      is_active: true,
+    };
+    let mut profiles: Vec<InstallProfileInfo> = self
+      .inactive_profiles
+      .iter()
+      .map(|(name, profile)| InstallProfileInfo {
+        name: name.clone(),
+        installation_dir: profile.installation_dir.clone(),
+        active_version: profile.active_version.clone(),
+        is_active: false,
+      })
+      .chain(std::iter::once(active))
+      .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+  }
+
+  fn has_profile(&self, name: &str) -> bool {
+    self.active_profile == name || self.inactive_profiles.contains_key(name)
+  }
+
+  pub fn create_profile(&mut self, name: String, installation_dir: Option<PathBuf>) -> Result<()> {
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+      bail!("Install profile names can't be empty");
+    }
+    if self.has_profile(&name) {
+      bail!("An install profile named '{name}' already exists");
+    }
+    if let Some(dir) = &installation_dir {
+      ensure_dir_writable(dir)?;
+    }
+
+    tracing::info!("Creating install profile '{name}'");
+    self.inactive_profiles.insert(
+      name,
+      InstallProfile {
+        installation_dir,
+        ..Default::default()
+      },
+    );
+    Ok(())
+  }
+
+  pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+    if self.active_profile == name {
+      return Ok(());
+    }
+    if self.overrides.overrides().overrides_installation() {
+      bail!("Install profiles can't be switched while the installation is overridden");
+    }
+    let next = self
+      .inactive_profiles
+      .remove(name)
+      .with_context(|| format!("No install profile named '{name}'"))?;
+
+    tracing::info!(
+      "Switching install profile from '{}' to '{name}'",
+      self.active_profile
+    );
+    let previous = InstallProfile {
+      installation_dir: std::mem::replace(&mut self.installation_dir, next.installation_dir),
+      active_version: std::mem::replace(&mut self.active_version, next.active_version),
+      games: std::mem::replace(&mut self.games, next.games),
+    };
+    let previous_name = std::mem::replace(&mut self.active_profile, name.to_owned());
+    self.inactive_profiles.insert(previous_name, previous);
+    Ok(())
+  }
+
+  pub fn delete_profile(&mut self, name: &str) -> Result<()> {
+    if self.active_profile == name {
+      bail!("The active install profile can't be deleted, switch to another one first");
+    }
+    self
+      .inactive_profiles
+      .remove(name)
+      .with_context(|| format!("No install profile named '{name}'"))?;
     Ok(())
   }
 
   pub fn set_install_directory(&mut self, path: PathBuf) -> Result<()> {
-    // Check our permissions on the folder by touching a file (and deleting it)
-    let test_file = path.join(".perm-test.tmp");
-    touch_file(&test_file).context("Provided installation folder could not be written to.")?;
-    let _ = fs::remove_file(&test_file);
+    ensure_dir_writable(&path)?;
 
     // If the directory changes (it's not a no-op), we need to:
     // - wipe any installed games (make them reinstall)
     // - wipe the active version/version types
     if self.installation_dir.as_ref() != Some(&path) {
-      self.active_version = None;
+      self.clear_active_version();
       self
         .games
         .values_mut()
@@ -306,41 +746,44 @@ impl LauncherConfig {
     }
 
     self.installation_dir = Some(path);
-    self.save_config()?;
     Ok(())
   }
 
-  pub fn set_game_installed(&mut self, game_name: SupportedGame, installed: bool) -> Result<()> {
-    let version = installed.then(|| self.active_version.clone()).flatten();
+  /// Points the config at an installation directory that the current install has been moved
+  /// into, unlike `set_install_directory` all of the install state is kept.
+  pub fn set_relocated_install_directory(&mut self, path: PathBuf) -> Result<()> {
+    ensure_dir_writable(&path)?;
+    self.installation_dir = Some(path);
+    Ok(())
+  }
+
+  pub fn set_game_installed(&mut self, game_name: SupportedGame, installed: bool) {
+    let version = installed.then(|| self.game_version(game_name)).flatten();
     self
       .get_supported_game_config_mut(game_name)
       .set_installed(installed)
       .set_version(version);
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn update_seconds_played(&mut self, game_name: SupportedGame, seconds: u64) -> Result<()> {
+  pub fn update_seconds_played(&mut self, game_name: SupportedGame, seconds: u64) {
     self
       .get_supported_game_config_mut(game_name)
       .update_seconds_played(seconds);
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_active_version(&mut self, version: Option<String>) -> Result<()> {
-    self.active_version = version;
-    self.save_config()?;
-    Ok(())
+  pub fn set_active_version(&mut self, channel: VersionChannel, version: String) {
+    self.active_version = Some(ChannelVersion::new(channel, version));
   }
 
-  pub fn set_locale(&mut self, locale: String) -> Result<()> {
+  pub fn clear_active_version(&mut self) {
+    self.active_version = None;
+  }
+
+  pub fn set_locale(&mut self, locale: String) {
     self.locale = locale;
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn update_mod_sources(&mut self, source: String, add: bool) -> Result<()> {
+  pub fn update_mod_sources(&mut self, source: String, add: bool) {
     if add {
       if !self.mod_sources.contains(&source) {
         self.mod_sources.push(source);
@@ -348,45 +791,49 @@ impl LauncherConfig {
     } else {
       self.mod_sources.retain(|s| s != &source);
     }
-
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_auto_update_games(&mut self, auto_update: bool) -> Result<()> {
+  pub fn set_auto_update_games(&mut self, auto_update: bool) {
     self.auto_update_games = auto_update;
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_check_for_latest_mod_version(&mut self, check: bool) -> Result<()> {
+  pub fn set_check_for_latest_mod_version(&mut self, check: bool) {
     self.check_for_latest_mod_version = check;
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_delete_previous_versions(&mut self, delete: bool) -> Result<()> {
+  pub fn set_delete_previous_versions(&mut self, delete: bool) {
     self.delete_previous_versions = delete;
-    self.save_config()?;
+  }
+
+  pub fn set_versions_to_keep(&mut self, count: u32) -> Result<()> {
+    if count == 0 {
+      bail!("At least one version has to be kept");
+    }
+    self.versions_to_keep = count;
     Ok(())
   }
 
-  pub fn set_hide_beta_alerts(&mut self, hide: bool) -> Result<()> {
+  pub fn release_index_url(&self) -> &str {
+    self
+      .release_index_url
+      .as_deref()
+      .unwrap_or(crate::releases::DEFAULT_RELEASE_INDEX_URL)
+  }
+
+  pub fn set_release_index_url(&mut self, url: Option<String>) {
+    self.release_index_url = url.filter(|url| !url.trim().is_empty());
+  }
+
+  pub fn set_hide_beta_alerts(&mut self, hide: bool) {
     self.ui.hide_beta_alerts = hide;
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_hide_gamename(&mut self, hide: bool) -> Result<()> {
+  pub fn set_hide_gamename(&mut self, hide: bool) {
     self.ui.hide_gamename = hide;
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn set_hide_playtime(&mut self, hide: bool) -> Result<()> {
+  pub fn set_hide_playtime(&mut self, hide: bool) {
     self.ui.hide_playtime = hide;
-    self.save_config()?;
-    Ok(())
   }
 
   pub fn add_mod(
@@ -395,46 +842,35 @@ impl LauncherConfig {
     source: String,
     version: String,
     mod_name: String,
-  ) -> Result<()> {
+  ) {
     self
       .get_supported_game_config_mut(game_name)
       .mods_installed_version
       .entry(source)
       .or_default()
       .insert(mod_name, version);
-    self.save_config()?;
-    Ok(())
   }
 
-  pub fn uninstall_mod(
-    &mut self,
-    game_name: SupportedGame,
-    source: String,
-    mod_name: String,
-  ) -> Result<()> {
+  pub fn uninstall_mod(&mut self, game_name: SupportedGame, source: String, mod_name: String) {
     self
       .get_supported_game_config_mut(game_name)
       .mods_installed_version
       .get_mut(&source)
       .map(|mods| mods.remove(&mod_name));
-    self.save_config()?;
-    Ok(())
   }
 
   pub fn cleanup_game_enabled_texture_packs(
     &mut self,
     game_name: SupportedGame,
     cleanup_list: Vec<String>,
-  ) -> Result<()> {
+  ) {
     if !cleanup_list.is_empty() {
-      return Ok(());
+      return;
     }
     self
       .get_supported_game_config_mut(game_name)
       .texture_packs
       .retain(|pack| !cleanup_list.contains(pack));
-    self.save_config()?;
-    Ok(())
   }
 
   pub fn install_dir(&self) -> Result<PathBuf> {
@@ -445,14 +881,28 @@ impl LauncherConfig {
       .ok_or_else(|| anyhow::anyhow!("No installation directory set"))
   }
 
-  pub fn active_version(&self) -> Result<String> {
+  pub fn active_version(&self) -> Result<&ChannelVersion> {
     self
       .active_version
       .as_ref()
-      .cloned()
       .ok_or_else(|| anyhow::anyhow!("No active version set"))
   }
 
+  pub fn version_dir(&self, channel: VersionChannel, version: &str) -> Result<PathBuf> {
+    Ok(
+      self
+        .install_dir()?
+        .join("versions")
+        .join(channel.to_string())
+        .join(version),
+    )
+  }
+
+  pub fn active_version_dir(&self) -> Result<PathBuf> {
+    let active_version = self.active_version()?;
+    self.version_dir(active_version.channel, &active_version.version)
+  }
+
   pub fn common_prelude(&self) -> Result<CommonConfigData, CommandError> {
     let install_path = self.installation_dir.as_ref().ok_or_else(|| {
       CommandError::BinaryExecution(
@@ -460,59 +910,196 @@ impl LauncherConfig {
       )
     })?;
 
-    let active_version = self.active_version.as_ref().ok_or_else(|| {
+    let ChannelVersion { channel, version } = self.active_version.as_ref().ok_or_else(|| {
       CommandError::BinaryExecution("No active version set, can't perform operation".to_owned())
     })?;
 
-    let version_str = active_version.strip_prefix('v').unwrap_or(active_version);
-
-    let tooling_version = Version::parse(version_str).unwrap_or_else(|_| Version::new(0, 1, 35));
+    let version_dir = install_path
+      .join("versions")
+      .join(channel.to_string())
+      .join(version);
 
     Ok(CommonConfigData {
       install_path: install_path.clone(),
-      active_version: active_version.clone(),
-      tooling_version,
+      active_version: version.clone(),
+      channel: *channel,
+      tooling_version: tooling_version(&version_dir, version),
+      version_dir,
+      bin_dir: install_path.join("active").join("bin"),
     })
   }
 
-  pub fn ensure_active_version_still_exists(&mut self) -> Result<bool> {
-    let Some(active_version) = &self.active_version else {
-      return Ok(false);
+  /// What the active version's binaries report, or what its name says if they were never probed
+  pub fn active_tooling_version(&self) -> Option<Version> {
+    let version_dir = self.active_version_dir().ok()?;
+    ToolingProbe::load(&version_dir)
+      .and_then(|probe| probe.version())
+      .or_else(|| parse_version_name(&self.active_version.as_ref()?.version))
+  }
+
+  /// The tooling version a game runs with, its pinned version if it has one
+  pub fn game_version(&self, game_name: SupportedGame) -> Option<ChannelVersion> {
+    self
+      .games
+      .get(&game_name)
+      .and_then(|game| game.pinned_version.clone())
+      .or_else(|| self.active_version.clone())
+  }
+
+  /// Like `common_prelude` but for operating on a specific game.  A game pinned to a version runs
+  /// that version's binaries straight from its folder, every other game runs the ones in
+  /// `active/bin` that `activate_version` and `rollback_active_version` swap in.
+  pub fn common_prelude_for_game(
+    &self,
+    game_name: SupportedGame,
+  ) -> Result<CommonConfigData, CommandError> {
+    let Some(pinned) = self
+      .games
+      .get(&game_name)
+      .and_then(|game| game.pinned_version.as_ref())
+    else {
+      return self.common_prelude();
     };
 
-    let version_dir = self
-      .install_dir()?
+    let install_path = self.installation_dir.as_ref().ok_or_else(|| {
+      CommandError::BinaryExecution(
+        "No installation directory set, can't perform operation".to_owned(),
+      )
+    })?;
+    let version_dir = install_path
       .join("versions")
-      .join("official")
-      .join(active_version);
+      .join(pinned.channel.to_string())
+      .join(&pinned.version);
+    if !version_dir.exists() {
+      return Err(CommandError::BinaryExecution(format!(
+        "{game_name} is pinned to version {pinned} which is not downloaded"
+      )));
+    }
 
-    if version_dir.exists() {
-      return Ok(true);
+    Ok(CommonConfigData {
+      install_path: install_path.clone(),
+      active_version: pinned.version.clone(),
+      channel: pinned.channel,
+      tooling_version: tooling_version(&version_dir, &pinned.version),
+      version_dir: version_dir.clone(),
+      bin_dir: version_dir,
+    })
+  }
+
+  pub fn outdated_games(&self) -> Vec<OutdatedGame> {
+    let mut outdated: Vec<OutdatedGame> = self
+      .games
+      .iter()
+      .filter(|(_, game)| game.is_installed)
+      .filter_map(|(&game_name, game)| {
+        let expected_version = self.game_version(game_name)?;
+        (game.version.as_ref() != Some(&expected_version)).then(|| OutdatedGame {
+          game: game_name,
+          installed_version: game.version.clone(),
+          expected_version,
+        })
+      })
+      .collect();
+    outdated.sort_by_key(|outdated| outdated.game.to_string());
+    outdated
+  }
+
+  pub fn pin_game_version(
+    &mut self,
+    game_name: SupportedGame,
+    channel: VersionChannel,
+    version: Option<String>,
+  ) -> Result<()> {
+    if let Some(version) = &version
+      && !self.version_dir(channel, version)?.exists()
+    {
+      bail!("Version {channel}/{version} is not downloaded");
     }
+    self.get_supported_game_config_mut(game_name).pinned_version =
+      version.map(|version| ChannelVersion::new(channel, version));
+    Ok(())
+  }
 
-    self.set_active_version(None)?;
-    Ok(false)
+  pub fn is_active_version(&self, channel: VersionChannel, version: &str) -> bool {
+    self
+      .active_version
+      .as_ref()
+      .is_some_and(|active_version| active_version.is(channel, version))
   }
 
-  pub fn remove_version(&mut self, version: &str) -> Result<()> {
-    let version_dir = self
-      .install_dir()?
-      .join("versions")
-      .join("official")
-      .join(version);
+  pub fn is_pinned_version(&self, channel: VersionChannel, version: &str) -> bool {
+    self.games.values().any(|game| {
+      game
+        .pinned_version
+        .as_ref()
+        .is_some_and(|pinned| pinned.is(channel, version))
+    })
+  }
 
-    delete_dir(&version_dir)?;
+  pub fn ensure_active_version_still_exists(&mut self) -> Result<bool> {
+    if self.active_version.is_none() {
+      return Ok(false);
+    }
+
+    if self.active_version_dir()?.exists() {
+      return Ok(true);
+    }
+
+    self.update(|config| {
+      config.clear_active_version();
+      Ok(())
+    })?;
+    Ok(false)
+  }
 
-    if self.active_version.as_deref() == Some(version) {
-      self.set_active_version(None)?;
-      delete_dir(&self.install_dir()?.join("active").join("bin"))?;
+  pub fn remove_version(&mut self, channel: VersionChannel, version: &str) -> Result<()> {
+    let is_active = self.is_active_version(channel, version);
+    // Nothing in the config points at the version anymore by the time its files are deleted
+    if is_active || self.is_pinned_version(channel, version) {
+      self.update(|config| {
+        for game in config.games.values_mut() {
+          if game
+            .pinned_version
+            .as_ref()
+            .is_some_and(|pinned| pinned.is(channel, version))
+          {
+            game.pinned_version = None;
+          }
+        }
+        if is_active {
+          config.clear_active_version();
+        }
+        Ok(())
+      })?;
     }
 
+    delete_dir(self.version_dir(channel, version)?)?;
+    if is_active {
+      delete_dir(self.install_dir()?.join("active").join("bin"))?;
+    }
     Ok(())
   }
 
-  pub fn list_downloaded_versions(&self, folder: &str) -> Result<Vec<String>> {
-    let dir = self.install_dir()?.join("versions").join(folder);
+  /// What cleaning up old versions would remove, without removing anything
+  pub fn version_retention_plan(&self) -> Result<RetentionPlan> {
+    retention::plan(self)
+  }
+
+  /// Removes the versions that fall outside of the retention policy, returning what was removed
+  pub fn apply_version_retention(&mut self) -> Result<RetentionPlan> {
+    let plan = retention::plan(self)?;
+    for removable in &plan.versions {
+      tracing::info!("Removing old version {}", removable.version);
+      self.remove_version(VersionChannel::Official, &removable.version)?;
+    }
+    Ok(plan)
+  }
+
+  pub fn list_downloaded_versions(&self, channel: VersionChannel) -> Result<Vec<String>> {
+    let dir = self
+      .install_dir()?
+      .join("versions")
+      .join(channel.to_string());
 
     Ok(
       std::fs::read_dir(&dir)
@@ -522,6 +1109,8 @@ impl LauncherConfig {
         .flatten()
         .filter(|e| e.path().is_dir())
         .filter_map(|e| e.file_name().into_string().ok())
+        // Imports in progress are staged in hidden folders next to the versions
+        .filter(|name| !name.starts_with('.'))
         .collect(),
     )
   }
@@ -533,24 +1122,145 @@ impl LauncherConfig {
       .is_some_and(|config| config.has_installed_mod(source, mod_name))
   }
 
-  pub fn copy_active_binaries(&self) -> anyhow::Result<()> {
+  /// Swaps the active version's binaries into `active/bin`, keeping the ones they replace around
+  /// for `rollback_active_version`
+  pub fn copy_active_binaries(&self) -> Result<()> {
     let install_dir = self.install_dir()?;
-    let version = self.active_version()?;
-
-    let src_dir = install_dir.join("versions").join("official").join(version);
+    let src_dir = self.active_version_dir()?;
     let dst_dir = install_dir.join("active").join("bin");
-    create_dir(&dst_dir)?;
+    let version_label = self.active_version()?.to_string();
 
-    for binary in ["gk", "extractor", "goalc"] {
-      let binary = if cfg!(windows) {
-        format!("{binary}.exe")
-      } else {
-        binary.to_string()
-      };
+    let binaries = tooling::BINARIES.map(tooling::binary_file_name);
+    activation::install(&src_dir, Some(&binaries), &dst_dir, &version_label)
+  }
 
-      std::fs::copy(src_dir.join(&binary), dst_dir.join(&binary))?;
+  /// Makes `version` the active version and swaps its binaries in.  The config is persisted first,
+  /// if the binaries can't be swapped in it's put back the way it was.
+  pub fn activate_version(&mut self, channel: VersionChannel, version: String) -> Result<()> {
+    let previous = self.active_version.clone();
+    self.update(|config| {
+      config.set_active_version(channel, version);
+      Ok(())
+    })?;
+    if let Err(err) = self.copy_active_binaries() {
+      self.revert_active_version(previous);
+      return Err(err);
     }
-
     Ok(())
   }
+
+  fn revert_active_version(&mut self, previous: Option<ChannelVersion>) {
+    let reverted = self.update(|config| {
+      config.active_version = previous;
+      Ok(())
+    });
+    if let Err(err) = reverted {
+      tracing::error!("Unable to put the active version back: {err:#}");
+    }
+  }
+
+  /// The version that was active before the current one, if its binaries are still around
+  pub fn previous_active_version(&self) -> Option<ChannelVersion> {
+    let bin_dir = self.install_dir().ok()?.join("active").join("bin");
+    activation::previous_version_label(&bin_dir)?.parse().ok()
+  }
+
+  /// Makes the previously active version active again, along with the data files it had
+  /// installed for each game.  Like `activate_version`, the config is persisted before any files
+  /// are swapped.
+  pub fn rollback_active_version(&mut self) -> Result<ChannelVersion> {
+    let Some(previous) = self.previous_active_version() else {
+      bail!("There is no previous version to go back to");
+    };
+    if !self
+      .version_dir(previous.channel, &previous.version)?
+      .exists()
+    {
+      bail!("Version {previous} has been removed since, it can't be restored");
+    }
+
+    let active_dir = self.install_dir()?.join("active");
+    let current = self.active_version.clone();
+    self.update(|config| {
+      config.active_version = Some(previous.clone());
+      Ok(())
+    })?;
+    if let Err(err) = activation::restore_previous(&active_dir.join("bin")) {
+      self.revert_active_version(current);
+      return Err(err);
+    }
+    let version_label = previous.to_string();
+    for game in SupportedGame::iter() {
+      let data_dir = active_dir.join(game.to_string()).join("data");
+      if activation::previous_version_label(&data_dir).as_deref() == Some(version_label.as_str())
+        && let Err(err) = activation::restore_previous(&data_dir)
+      {
+        tracing::warn!("Unable to restore the previous data files for {game}: {err:#}");
+      }
+    }
+    Ok(previous)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn versions_from_different_channels_dont_match() {
+    let mut config: LauncherConfig = serde_json::from_value(json!({
+      "version": migrations::CURRENT_VERSION,
+      "games": {
+        "jak1": {
+          "isInstalled": true,
+          "version": { "channel": "official", "version": "v0.2.0" }
+        }
+      },
+      "installationDir": "/opt/opengoal",
+      "activeVersion": { "channel": "unofficial", "version": "v0.2.0" }
+    }))
+    .unwrap();
+    let outdated = config.outdated_games();
+    assert_eq!(outdated.len(), 1);
+    assert_eq!(
+      outdated[0].expected_version,
+      ChannelVersion::new(VersionChannel::Unofficial, "v0.2.0".to_owned())
+    );
+
+    config
+      .get_supported_game_config_mut(SupportedGame::Jak1)
+      .pinned_version = Some(ChannelVersion::new(
+      VersionChannel::Official,
+      "v0.2.0".to_owned(),
+    ));
+    assert!(config.outdated_games().is_empty());
+    assert!(config.is_pinned_version(VersionChannel::Official, "v0.2.0"));
+    assert!(!config.is_pinned_version(VersionChannel::Unofficial, "v0.2.0"));
+  }
+
+  #[test]
+  fn only_pinned_games_run_outside_of_active_bin() {
+    let install_dir = tempfile::tempdir().unwrap();
+    let versions_dir = install_dir.path().join("versions").join("official");
+    for version in ["v0.2.0", "v0.1.40"] {
+      std::fs::create_dir_all(versions_dir.join(version)).unwrap();
+    }
+    let config: LauncherConfig = serde_json::from_value(json!({
+      "version": migrations::CURRENT_VERSION,
+      "games": {
+        "jak1": { "pinnedVersion": { "channel": "official", "version": "v0.1.40" } }
+      },
+      "installationDir": install_dir.path(),
+      "activeVersion": { "channel": "official", "version": "v0.2.0" }
+    }))
+    .unwrap();
+
+    // What rollbacks swap in has to be what actually runs
+    let jak2 = config.common_prelude_for_game(SupportedGame::Jak2).unwrap();
+    assert_eq!(jak2.bin_dir, install_dir.path().join("active").join("bin"));
+    let jak1 = config.common_prelude_for_game(SupportedGame::Jak1).unwrap();
+    assert_eq!(jak1.bin_dir, versions_dir.join("v0.1.40"));
+  }
 }
@@ -0,0 +1,115 @@
+// Rotating history of `settings.json`
+//
+// Copies live under `<config dir>/backups/settings-<unix millis>.json`, only the most recent
+// `MAX_BACKUPS` are kept around.
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ts_rs::TS;
+
+use crate::util::file::{create_dir, write_file_atomic};
+
+const MAX_BACKUPS: usize = 5;
+const BACKUP_PREFIX: &str = "settings-";
+const BACKUP_EXTENSION: &str = "json";
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SettingsBackup {
+  pub file_name: String,
+  pub created_at: u64,
+  pub size_bytes: u64,
+}
+
+fn backup_dir(settings_path: &Path) -> PathBuf {
+  settings_path.with_file_name("backups")
+}
+
+fn backup_timestamp(file_name: &str) -> Option<u64> {
+  file_name
+    .strip_prefix(BACKUP_PREFIX)?
+    .strip_suffix(&format!(".{BACKUP_EXTENSION}"))?
+    .parse()
+    .ok()
+}
+
+/// Lists the available backups, newest first
+pub fn list_backups(settings_path: &Path) -> Result<Vec<SettingsBackup>> {
+  let entries = match fs::read_dir(backup_dir(settings_path)) {
+    Ok(entries) => entries,
+    Err(_) => return Ok(Vec::new()),
+  };
+
+  let mut backups: Vec<SettingsBackup> = entries
+    .flatten()
+    .filter_map(|entry| {
+      let file_name = entry.file_name().into_string().ok()?;
+      let created_at = backup_timestamp(&file_name)?;
+      let size_bytes = entry.metadata().ok()?.len();
+      Some(SettingsBackup {
+        file_name,
+        created_at,
+        size_bytes,
+      })
+    })
+    .collect();
+  backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+  Ok(backups)
+}
+
+/// Copies the current settings file into the backup folder and prunes the oldest copies.
+///
+/// Nothing is written if the settings file is identical to the most recent backup.
+pub fn create_backup(settings_path: &Path) -> Result<()> {
+  let contents = match fs::read(settings_path) {
+    Ok(contents) => contents,
+    Err(_) => return Ok(()),
+  };
+
+  let dir = backup_dir(settings_path);
+  create_dir(&dir)?;
+
+  let backups = list_backups(settings_path)?;
+  if let Some(latest) = backups.first()
+    && fs::read(dir.join(&latest.file_name)).is_ok_and(|latest| latest == contents)
+  {
+    return Ok(());
+  }
+
+  // Guarantee ordering even if two backups are taken within the same millisecond
+  let now = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or_default();
+  let created_at = backups
+    .first()
+    .map_or(now, |latest| now.max(latest.created_at + 1));
+  let file_name = format!("{BACKUP_PREFIX}{created_at}.{BACKUP_EXTENSION}");
+
+  tracing::info!("Backing up settings to {file_name}");
+  write_file_atomic(dir.join(&file_name), &contents)?;
+
+  for old_backup in list_backups(settings_path)?.iter().skip(MAX_BACKUPS) {
+    tracing::info!("Removing old settings backup {}", old_backup.file_name);
+    let _ = fs::remove_file(dir.join(&old_backup.file_name));
+  }
+  Ok(())
+}
+
+/// Reads the contents of a backup, `file_name` has to be one returned from `list_backups`
+pub fn read_backup(settings_path: &Path, file_name: &str) -> Result<String> {
+  if !list_backups(settings_path)?
+    .iter()
+    .any(|backup| backup.file_name == file_name)
+  {
+    bail!("No settings backup named '{file_name}'");
+  }
+
+  let path = backup_dir(settings_path).join(file_name);
+  fs::read_to_string(&path)
+    .with_context(|| format!("Unable to read settings backup {}", path.display()))
+}
@@ -0,0 +1,208 @@
+// Cross-checks what `settings.json` claims is installed against what is actually on disk
+//
+// Things get out of sync whenever files are removed behind the launcher's back (antivirus,
+// manually cleaning up the install folder, a failed update, etc).  Every problem found comes
+// with the fix that would bring the config back in line with the filesystem.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+use ts_rs::TS;
+
+use super::{LauncherConfig, SupportedGame, VersionChannel};
+use crate::util::tooling;
+
+#[derive(Debug, Serialize, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+#[ts(export)]
+pub enum MissingContent {
+  ActiveVersion {
+    version: String,
+  },
+  ActiveBinaries {
+    binaries: Vec<String>,
+  },
+  GameData {
+    game: SupportedGame,
+  },
+  PinnedVersion {
+    game: SupportedGame,
+    version: String,
+  },
+  Mod {
+    game: SupportedGame,
+    source: String,
+    name: String,
+  },
+  TexturePack {
+    game: SupportedGame,
+    name: String,
+  },
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+#[ts(export)]
+pub enum InstallFix {
+  ClearActiveVersion,
+  RestoreActiveBinaries,
+  MarkGameUninstalled {
+    game: SupportedGame,
+  },
+  UnpinGameVersion {
+    game: SupportedGame,
+  },
+  ForgetMod {
+    game: SupportedGame,
+    source: String,
+    name: String,
+  },
+  DisableTexturePack {
+    game: SupportedGame,
+    name: String,
+  },
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct InstallProblem {
+  pub missing: MissingContent,
+  pub path: PathBuf,
+  pub suggested_fix: InstallFix,
+}
+
+/// Walks the config and the installation directory, returning everything that doesn't line up.
+pub fn diagnose(config: &LauncherConfig) -> Result<Vec<InstallProblem>> {
+  let mut problems = Vec::new();
+  let Some(install_dir) = &config.installation_dir else {
+    return Ok(problems);
+  };
+
+  if let Some(version) = &config.active_version {
+    let version_dir = config.active_version_dir()?;
+    if !version_dir.exists() {
+      problems.push(InstallProblem {
+        missing: MissingContent::ActiveVersion {
+          version: version.version.clone(),
+        },
+        path: version_dir,
+        suggested_fix: InstallFix::ClearActiveVersion,
+      });
+    } else {
+      // Only worth checking if there is a version to restore them from
+      let bin_dir = install_dir.join("active").join("bin");
+      let missing = tooling::missing_binaries(&bin_dir);
+      if !missing.is_empty() {
+        problems.push(InstallProblem {
+          missing: MissingContent::ActiveBinaries { binaries: missing },
+          path: bin_dir,
+          suggested_fix: InstallFix::RestoreActiveBinaries,
+        });
+      }
+    }
+  }
+
+  let mut games: Vec<_> = config.games.iter().collect();
+  games.sort_by_key(|(game, _)| game.to_string());
+  for (&game, game_config) in games {
+    let game_dir = install_dir.join("active").join(game.to_string());
+    let out_dir = game_dir.join("data").join("out");
+    if game_config.is_installed && !out_dir.exists() {
+      problems.push(InstallProblem {
+        missing: MissingContent::GameData { game },
+        path: out_dir,
+        suggested_fix: InstallFix::MarkGameUninstalled { game },
+      });
+    }
+
+    if let Some(version) = &game_config.pinned_version {
+      let version_dir = config.version_dir(version.channel, &version.version)?;
+      if !version_dir.exists() {
+        problems.push(InstallProblem {
+          missing: MissingContent::PinnedVersion {
+            game,
+            version: version.version.clone(),
+          },
+          path: version_dir,
+          suggested_fix: InstallFix::UnpinGameVersion { game },
+        });
+      }
+    }
+
+    let features_dir = install_dir.join("features").join(game.to_string());
+    let mut sources: Vec<_> = game_config.mods_installed_version.iter().collect();
+    sources.sort_by_key(|(source, _)| *source);
+    for (source, mods) in sources {
+      let mut mod_names: Vec<_> = mods.keys().collect();
+      mod_names.sort();
+      for name in mod_names {
+        let mod_dir = features_dir.join("mods").join(source).join(name);
+        if !mod_dir.exists() {
+          problems.push(InstallProblem {
+            missing: MissingContent::Mod {
+              game,
+              source: source.clone(),
+              name: name.clone(),
+            },
+            path: mod_dir,
+            suggested_fix: InstallFix::ForgetMod {
+              game,
+              source: source.clone(),
+              name: name.clone(),
+            },
+          });
+        }
+      }
+    }
+
+    for pack in &game_config.texture_packs {
+      let pack_dir = features_dir.join("texture-packs").join(pack);
+      if !pack_dir.exists() {
+        problems.push(InstallProblem {
+          missing: MissingContent::TexturePack {
+            game,
+            name: pack.clone(),
+          },
+          path: pack_dir,
+          suggested_fix: InstallFix::DisableTexturePack {
+            game,
+            name: pack.clone(),
+          },
+        });
+      }
+    }
+  }
+
+  Ok(problems)
+}
+
+/// Applies the given fixes, config changes are persisted together as a single update.
+pub fn apply_fixes(config: &mut LauncherConfig, fixes: &[InstallFix]) -> Result<()> {
+  config.update(|config| {
+    for fix in fixes {
+      tracing::info!("Applying install fix: {fix:?}");
+      match fix {
+        InstallFix::ClearActiveVersion => config.clear_active_version(),
+        InstallFix::MarkGameUninstalled { game } => config.set_game_installed(*game, false),
+        InstallFix::UnpinGameVersion { game } => {
+          config.pin_game_version(*game, VersionChannel::default(), None)?
+        }
+        InstallFix::ForgetMod { game, source, name } => {
+          config.uninstall_mod(*game, source.clone(), name.clone())
+        }
+        InstallFix::DisableTexturePack { game, name } => config
+          .get_supported_game_config_mut(*game)
+          .texture_packs
+          .retain(|pack| pack != name),
+        InstallFix::RestoreActiveBinaries => {}
+      }
+    }
+    Ok(())
+  })?;
+
+  if fixes.contains(&InstallFix::RestoreActiveBinaries) && config.active_version.is_some() {
+    config.copy_active_binaries()?;
+  }
+  Ok(())
+}
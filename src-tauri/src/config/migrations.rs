@@ -0,0 +1,340 @@
+// Upgrades older `settings.json` layouts to the current one
+//
+// Migrations operate on the raw json rather than on typed structs, that way we don't have to keep
+// every historical version of `LauncherConfig` around just to read it once.
+//
+// To reshape the config:
+// - bump `CURRENT_VERSION`
+// - append a migration from the previous version to the new one at the end of `MIGRATIONS`
+//
+// Migrations are applied in order, starting from the one matching the file's `version`
+
+use anyhow::{Result, bail};
+use serde_json::{Map, Value};
+
+pub const CURRENT_VERSION: &str = "4.0";
+
+// The very first config layout did not always write a version out
+const UNVERSIONED: &str = "1.0";
+
+struct Migration {
+  from: &'static str,
+  to: &'static str,
+  apply: fn(&mut Map<String, Value>) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+  Migration {
+    from: "1.0",
+    to: "2.0",
+    apply: v1_to_v2,
+  },
+  Migration {
+    from: "2.0",
+    to: "3.0",
+    apply: v2_to_v3,
+  },
+  Migration {
+    from: "3.0",
+    to: "4.0",
+    apply: v3_to_v4,
+  },
+];
+
+pub fn config_version(config: &Value) -> String {
+  config
+    .get("version")
+    .and_then(Value::as_str)
+    .unwrap_or(UNVERSIONED)
+    .to_owned()
+}
+
+/// Applies every migration needed to bring `config` up to `CURRENT_VERSION`.
+///
+/// Returns the version the config started at if anything was changed.
+pub fn migrate(config: &mut Value) -> Result<Option<String>> {
+  let original_version = config_version(config);
+  let Some(start) = MIGRATIONS.iter().position(|m| m.from == original_version) else {
+    if original_version != CURRENT_VERSION {
+      tracing::warn!(
+        "Settings file has unknown version '{original_version}', attempting to load it as-is"
+      );
+    }
+    return Ok(None);
+  };
+
+  let Some(object) = config.as_object_mut() else {
+    bail!("Settings file is not a json object");
+  };
+
+  for migration in &MIGRATIONS[start..] {
+    tracing::info!(
+      "Migrating settings from version {} to {}",
+      migration.from,
+      migration.to
+    );
+    (migration.apply)(object)?;
+    object.insert("version".to_owned(), Value::from(migration.to));
+  }
+
+  Ok(Some(original_version))
+}
+
+fn take_object(object: &mut Map<String, Value>, key: &str) -> Map<String, Value> {
+  match object.remove(key) {
+    Some(Value::Object(map)) => map,
+    _ => Map::new(),
+  }
+}
+
+// 1.0
+// - games were keyed by their display name ("Jak 1") instead of their id ("jak1")
+// - each game and the config itself tracked a `versionFolder`, only "official" was ever used
+// - `lastActiveGame` was tracked but no longer does anything
+fn v1_to_v2(config: &mut Map<String, Value>) -> Result<()> {
+  let games = take_object(config, "games")
+    .into_iter()
+    .map(|(name, mut game)| {
+      let id = match name.as_str() {
+        "Jak 1" => "jak1".to_owned(),
+        "Jak 2" => "jak2".to_owned(),
+        "Jak 3" => "jak3".to_owned(),
+        "Jak X" => "jakx".to_owned(),
+        _ => name,
+      };
+      if let Some(game) = game.as_object_mut() {
+        game.remove("versionFolder");
+      }
+      (id, game)
+    })
+    .collect();
+  config.insert("games".to_owned(), Value::Object(games));
+  config.remove("activeVersionFolder");
+  config.remove("lastActiveGame");
+  Ok(())
+}
+
+// 2.0
+// - enabled texture packs were nested under `features.texturePacks`
+// - installed mods were stored under `modsInstalledVersion`
+// - the ui toggles lived at the top level instead of under `ui`
+fn v2_to_v3(config: &mut Map<String, Value>) -> Result<()> {
+  if let Some(Value::Object(games)) = config.get_mut("games") {
+    for game in games.values_mut().filter_map(Value::as_object_mut) {
+      let mut features = take_object(game, "features");
+      if let Some(texture_packs) = features.remove("texturePacks") {
+        game.insert("texturePacks".to_owned(), texture_packs);
+      }
+      if let Some(mods) = game.remove("modsInstalledVersion") {
+        game.insert("mods".to_owned(), mods);
+      }
+    }
+  }
+
+  let mut ui = take_object(config, "ui");
+  for key in ["hideBetaAlerts", "hideGamename", "hidePlaytime"] {
+    if let Some(value) = config.remove(key) {
+      ui.insert(key.to_owned(), value);
+    }
+  }
+  config.insert("ui".to_owned(), Value::Object(ui));
+  Ok(())
+}
+
+// 3.0
+// - the active version and the version each game was installed with were bare version names,
+//   every version came from the official releases
+fn v3_to_v4(config: &mut Map<String, Value>) -> Result<()> {
+  fn with_channel(version: Option<Value>) -> Value {
+    match version {
+      Some(Value::String(version)) => {
+        serde_json::json!({ "channel": "official", "version": version })
+      }
+      _ => Value::Null,
+    }
+  }
+
+  let active_version = with_channel(config.remove("activeVersion"));
+  config.insert("activeVersion".to_owned(), active_version);
+  if let Some(Value::Object(games)) = config.get_mut("games") {
+    for game in games.values_mut().filter_map(Value::as_object_mut) {
+      let version = with_channel(game.remove("version"));
+      game.insert("version".to_owned(), version);
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::config::{LauncherConfig, SupportedGame, VersionChannel};
+
+  fn migrated(mut config: Value) -> Value {
+    migrate(&mut config).unwrap();
+    config
+  }
+
+  #[test]
+  fn migrates_v1_layout() {
+    let config = migrated(json!({
+      "requirements": { "avx": true, "openGL": true },
+      "games": {
+        "Jak 1": {
+          "isInstalled": true,
+          "versionFolder": "official",
+          "version": "v0.1.32",
+          "features": { "texturePacks": ["hd-hud"] },
+          "modsInstalledVersion": { "community": { "randomizer": "1.2.0" } }
+        },
+        "Jak 2": { "isInstalled": false, "versionFolder": null }
+      },
+      "installationDir": "/opt/opengoal",
+      "activeVersion": "v0.1.32",
+      "activeVersionFolder": "official",
+      "lastActiveGame": "Jak 1",
+      "locale": "en-US",
+      "hideBetaAlerts": true
+    }));
+
+    assert_eq!(
+      config,
+      json!({
+        "version": CURRENT_VERSION,
+        "requirements": { "avx": true, "openGL": true },
+        "games": {
+          "jak1": {
+            "isInstalled": true,
+            "version": { "channel": "official", "version": "v0.1.32" },
+            "texturePacks": ["hd-hud"],
+            "mods": { "community": { "randomizer": "1.2.0" } }
+          },
+          "jak2": { "isInstalled": false, "version": null }
+        },
+        "installationDir": "/opt/opengoal",
+        "activeVersion": { "channel": "official", "version": "v0.1.32" },
+        "locale": "en-US",
+        "ui": { "hideBetaAlerts": true }
+      })
+    );
+
+    let config: LauncherConfig = serde_json::from_value(config).unwrap();
+    assert_eq!(config.version, CURRENT_VERSION);
+    let jak1 = &config.games[&SupportedGame::Jak1];
+    assert!(jak1.is_installed);
+    assert_eq!(jak1.texture_packs, ["hd-hud"]);
+    assert!(jak1.has_installed_mod("community", "randomizer"));
+    assert!(config.ui.hide_beta_alerts);
+  }
+
+  #[test]
+  fn migrates_v2_layout() {
+    let config = migrated(json!({
+      "version": "2.0",
+      "games": {
+        "jak1": {
+          "isInstalled": true,
+          "features": { "texturePacks": ["hd-hud", "retro"] },
+          "modsInstalledVersion": {}
+        },
+        "jak3": { "isInstalled": false, "features": {} }
+      },
+      "installationDir": "/opt/opengoal",
+      "activeVersion": "v0.2.1",
+      "hideBetaAlerts": false,
+      "hideGamename": true,
+      "hidePlaytime": true
+    }));
+
+    assert_eq!(
+      config,
+      json!({
+        "version": CURRENT_VERSION,
+        "games": {
+          "jak1": {
+            "isInstalled": true,
+            "version": null,
+            "texturePacks": ["hd-hud", "retro"],
+            "mods": {}
+          },
+          "jak3": { "isInstalled": false, "version": null }
+        },
+        "installationDir": "/opt/opengoal",
+        "activeVersion": { "channel": "official", "version": "v0.2.1" },
+        "ui": { "hideBetaAlerts": false, "hideGamename": true, "hidePlaytime": true }
+      })
+    );
+
+    let config: LauncherConfig = serde_json::from_value(config).unwrap();
+    assert_eq!(
+      config.games[&SupportedGame::Jak1].texture_packs,
+      ["hd-hud", "retro"]
+    );
+    assert!(!config.games[&SupportedGame::Jak3].is_installed);
+    assert!(config.ui.hide_gamename && config.ui.hide_playtime);
+  }
+
+  #[test]
+  fn migrates_v3_layout() {
+    // As written by the last launcher release before channels
+    let config = migrated(json!({
+      "version": "3.0",
+      "requirements": { "bypassRequirements": false, "avx": true, "openGL": true },
+      "games": {
+        "jak1": {
+          "isInstalled": true,
+          "version": "v0.2.14",
+          "texturePacks": [],
+          "secondsPlayed": 5400,
+          "mods": {}
+        },
+        "jak2": {
+          "isInstalled": false,
+          "version": null,
+          "texturePacks": [],
+          "secondsPlayed": 0,
+          "mods": {}
+        }
+      },
+      "installationDir": "/opt/opengoal",
+      "activeVersion": "v0.2.14",
+      "locale": "en-US",
+      "modSources": [],
+      "decompilerSettings": {
+        "ripLevelsEnabled": false,
+        "ripCollisionEnabled": false,
+        "ripTexturesEnabled": false,
+        "ripStreamedAudioEnabled": false
+      },
+      "checkForLatestModVersion": true,
+      "proceedAfterSuccessfulOperation": true,
+      "autoUpdateGames": false,
+      "deletePreviousVersions": false,
+      "ui": { "hideBetaAlerts": false, "hideGamename": false, "hidePlaytime": false }
+    }));
+
+    let official = |version: &str| json!({ "channel": "official", "version": version });
+    assert_eq!(config["version"], CURRENT_VERSION);
+    assert_eq!(config["activeVersion"], official("v0.2.14"));
+    assert_eq!(config["games"]["jak1"]["version"], official("v0.2.14"));
+    assert_eq!(config["games"]["jak2"]["version"], Value::Null);
+
+    let config: LauncherConfig = serde_json::from_value(config).unwrap();
+    assert!(config.is_active_version(VersionChannel::Official, "v0.2.14"));
+    assert_eq!(config.games[&SupportedGame::Jak1].seconds_played, 5400);
+    // Installed with the version that's still active, so there's nothing to rebuild
+    assert!(config.outdated_games().is_empty());
+  }
+
+  #[test]
+  fn leaves_current_layout_alone() {
+    let mut config = json!({ "version": CURRENT_VERSION, "locale": "de-DE" });
+    assert_eq!(migrate(&mut config).unwrap(), None);
+    assert_eq!(
+      config,
+      json!({ "version": CURRENT_VERSION, "locale": "de-DE" })
+    );
+  }
+}
@@ -0,0 +1,201 @@
+// Session-only overrides layered on top of `settings.json`
+//
+// Intended for automated test rigs, a handful of settings can be forced through environment
+// variables or command-line flags.  Flags win over environment variables:
+//
+// | setting               | environment variable                   | flag                     |
+// |-----------------------|----------------------------------------|--------------------------|
+// | installationDir       | OPENGOAL_LAUNCHER_INSTALLATION_DIR     | --installation-dir       |
+// | activeVersion         | OPENGOAL_LAUNCHER_ACTIVE_VERSION       | --active-version         |
+// | modSources            | OPENGOAL_LAUNCHER_MOD_SOURCES          | --mod-sources            |
+// | decompilerSettings    | OPENGOAL_LAUNCHER_DECOMPILER_SETTINGS  | --decompiler-settings    |
+// | requirements          | OPENGOAL_LAUNCHER_REQUIREMENTS         | --requirements           |
+// | releaseIndexUrl       | OPENGOAL_LAUNCHER_RELEASE_INDEX_URL    | --release-index-url      |
+//
+// The active version is given as `<channel>/<version>`, or just the version for an official one.
+// Mod sources are comma separated, decompiler settings and requirements are json objects in the
+// same shape as they appear in `settings.json`.
+//
+// Overridden values are never written back, whatever was in the file is written instead.  Changes
+// made to an overridden setting while the launcher is running only last for that session.
+
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+
+use super::{ChannelVersion, DecompilerSettings, LauncherConfig, Requirements, VersionChannel};
+
+const ENV_PREFIX: &str = "OPENGOAL_LAUNCHER_";
+
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+  installation_dir: Option<PathBuf>,
+  active_version: Option<ChannelVersion>,
+  mod_sources: Option<Vec<String>>,
+  decompiler_settings: Option<DecompilerSettings>,
+  requirements: Option<Requirements>,
+  release_index_url: Option<String>,
+}
+
+// Looks for `--name value` or `--name=value`
+fn find_flag(args: &[String], name: &str) -> Option<String> {
+  let flag = format!("--{name}");
+  args.iter().enumerate().find_map(|(index, arg)| {
+    if *arg == flag {
+      args.get(index + 1).cloned()
+    } else {
+      arg
+        .strip_prefix(&flag)
+        .and_then(|rest| rest.strip_prefix('='))
+        .map(str::to_owned)
+    }
+  })
+}
+
+fn parse_json<T: DeserializeOwned>(name: &str, value: &str) -> Option<T> {
+  serde_json::from_str(value)
+    .inspect_err(|err| tracing::warn!("Ignoring invalid '{name}' override: {err}"))
+    .ok()
+}
+
+impl ConfigOverrides {
+  /// Collects overrides from the process's environment and command-line arguments
+  pub fn from_env() -> Self {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let lookup = |name: &str| {
+      find_flag(&args, name).or_else(|| {
+        std::env::var(format!(
+          "{ENV_PREFIX}{}",
+          name.replace('-', "_").to_uppercase()
+        ))
+        .ok()
+      })
+    };
+
+    let installation_dir = lookup("installation-dir").and_then(|dir| {
+      std::path::absolute(&dir)
+        .inspect_err(|err| tracing::warn!("Ignoring invalid 'installation-dir' override: {err}"))
+        .ok()
+    });
+    let active_version = lookup("active-version")
+      .filter(|version| !version.is_empty())
+      .and_then(|version| {
+        if !version.contains('/') {
+          return Some(ChannelVersion::new(VersionChannel::Official, version));
+        }
+        version
+          .parse()
+          .inspect_err(|err| tracing::warn!("Ignoring invalid 'active-version' override: {err}"))
+          .ok()
+      });
+    let mod_sources = lookup("mod-sources").map(|sources| {
+      sources
+        .split(',')
+        .map(str::trim)
+        .filter(|source| !source.is_empty())
+        .map(str::to_owned)
+        .collect()
+    });
+
+    Self {
+      installation_dir,
+      active_version,
+      mod_sources,
+      decompiler_settings: lookup("decompiler-settings")
+        .and_then(|value| parse_json("decompiler-settings", &value)),
+      requirements: lookup("requirements").and_then(|value| parse_json("requirements", &value)),
+      release_index_url: lookup("release-index-url").filter(|url| !url.is_empty()),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields().is_empty()
+  }
+
+  /// The settings being overridden, named as they are in `settings.json`
+  pub fn fields(&self) -> Vec<String> {
+    [
+      ("installationDir", self.installation_dir.is_some()),
+      ("activeVersion", self.active_version.is_some()),
+      ("modSources", self.mod_sources.is_some()),
+      ("decompilerSettings", self.decompiler_settings.is_some()),
+      ("requirements", self.requirements.is_some()),
+      ("releaseIndexUrl", self.release_index_url.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, overridden)| *overridden)
+    .map(|(name, _)| name.to_owned())
+    .collect()
+  }
+
+  pub fn overrides_installation(&self) -> bool {
+    self.installation_dir.is_some() || self.active_version.is_some()
+  }
+}
+
+/// Overrides currently applied to a config, along with the values they replaced
+#[derive(Debug, Default, Clone)]
+pub struct OverrideLayer {
+  overrides: ConfigOverrides,
+  persisted: ConfigOverrides,
+}
+
+impl OverrideLayer {
+  pub fn overrides(&self) -> &ConfigOverrides {
+    &self.overrides
+  }
+
+  /// Puts the overridden values into `config`, remembering what it held before
+  pub fn apply(overrides: ConfigOverrides, config: &mut LauncherConfig) -> Self {
+    let mut persisted = ConfigOverrides::default();
+    if let Some(dir) = &overrides.installation_dir {
+      persisted.installation_dir = config.installation_dir.replace(dir.clone());
+    }
+    if let Some(version) = &overrides.active_version {
+      persisted.active_version = config.active_version.replace(version.clone());
+    }
+    if let Some(sources) = &overrides.mod_sources {
+      persisted.mod_sources = Some(std::mem::replace(&mut config.mod_sources, sources.clone()));
+    }
+    if let Some(settings) = &overrides.decompiler_settings {
+      persisted.decompiler_settings = Some(std::mem::replace(
+        &mut config.decompiler_settings,
+        settings.clone(),
+      ));
+    }
+    if let Some(requirements) = &overrides.requirements {
+      persisted.requirements = Some(std::mem::replace(
+        &mut config.requirements,
+        requirements.clone(),
+      ));
+    }
+    if let Some(url) = &overrides.release_index_url {
+      persisted.release_index_url = config.release_index_url.replace(url.clone());
+    }
+    Self {
+      overrides,
+      persisted,
+    }
+  }
+
+  /// Swaps the values that were in the file back into `config`, ready to be written out
+  pub fn restore(&self, config: &mut LauncherConfig) {
+    if self.overrides.installation_dir.is_some() {
+      config.installation_dir = self.persisted.installation_dir.clone();
+    }
+    if self.overrides.active_version.is_some() {
+      config.active_version = self.persisted.active_version.clone();
+    }
+    if let Some(sources) = &self.persisted.mod_sources {
+      config.mod_sources = sources.clone();
+    }
+    if let Some(settings) = &self.persisted.decompiler_settings {
+      config.decompiler_settings = settings.clone();
+    }
+    if let Some(requirements) = &self.persisted.requirements {
+      config.requirements = requirements.clone();
+    }
+    if self.overrides.release_index_url.is_some() {
+      config.release_index_url = self.persisted.release_index_url.clone();
+    }
+  }
+}
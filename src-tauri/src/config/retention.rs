@@ -0,0 +1,66 @@
+// Decides which downloaded official tooling versions can be removed
+//
+// The newest `versionsToKeep` versions are kept, along with the active version and any version a
+// game is pinned to regardless of how old they are.  Folders that aren't named after a version
+// weren't downloaded by the launcher and are left alone.
+
+use anyhow::Result;
+use semver::Version;
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::{LauncherConfig, VersionChannel};
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RemovableVersion {
+  pub version: String,
+  pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct RetentionPlan {
+  pub versions: Vec<RemovableVersion>,
+  pub freed_bytes: u64,
+}
+
+pub fn plan(config: &LauncherConfig) -> Result<RetentionPlan> {
+  let mut downloaded: Vec<(Version, String)> = config
+    .list_downloaded_versions(VersionChannel::Official)?
+    .into_iter()
+    .filter_map(|name| {
+      Version::parse(name.trim_start_matches('v'))
+        .ok()
+        .map(|version| (version, name))
+    })
+    .collect();
+  downloaded.sort_by(|a, b| b.0.cmp(&a.0));
+
+  let versions: Vec<RemovableVersion> = downloaded
+    .into_iter()
+    .skip(config.versions_to_keep as usize)
+    .filter(|(_, name)| {
+      !config.is_active_version(VersionChannel::Official, name)
+        && !config.is_pinned_version(VersionChannel::Official, name)
+    })
+    .map(|(_, name)| {
+      let size_bytes = config
+        .version_dir(VersionChannel::Official, &name)
+        .and_then(|dir| Ok(fs_extra::dir::get_size(dir)?))
+        .inspect_err(|err| tracing::warn!("Unable to determine the size of {name}: {err}"))
+        .unwrap_or_default();
+      RemovableVersion {
+        version: name,
+        size_bytes,
+      }
+    })
+    .collect();
+
+  Ok(RetentionPlan {
+    freed_bytes: versions.iter().map(|version| version.size_bytes).sum(),
+    versions,
+  })
+}
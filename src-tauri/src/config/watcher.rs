@@ -0,0 +1,79 @@
+// Picks up edits made to `settings.json` outside of the launcher
+//
+// The parent directory is watched rather than the file itself, saving the config replaces the
+// file (see `write_file_atomic`) which would otherwise silently end the watch.  Our own writes
+// trigger events too, those are ignored as the file already matches the managed config.
+
+use anyhow::Result;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::time::Duration;
+use tauri::Manager;
+
+use super::LauncherConfig;
+use crate::util::file::create_dir;
+
+// Editors tend to save in several steps, give them a moment to finish
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+/// Keeps the watch alive for as long as it is held onto
+pub struct SettingsWatcher {
+  _watcher: RecommendedWatcher,
+}
+
+pub fn watch(app_handle: tauri::AppHandle, settings_path: &Path) -> Result<SettingsWatcher> {
+  let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+  let file_name = settings_path.file_name().map(|name| name.to_owned());
+  let mut watcher =
+    notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+      Ok(event) => {
+        let touches_settings = event
+          .paths
+          .iter()
+          .any(|path| path.file_name() == file_name.as_deref());
+        if touches_settings && (event.kind.is_create() || event.kind.is_modify()) {
+          let _ = sender.send(());
+        }
+      }
+      Err(err) => tracing::warn!("Settings watcher error: {err}"),
+    })?;
+
+  let watch_dir = settings_path
+    .parent()
+    .ok_or_else(|| anyhow::anyhow!("Settings file has no parent directory"))?;
+  create_dir(watch_dir)?;
+  watcher.watch(watch_dir, RecursiveMode::NonRecursive)?;
+  tracing::info!("Watching {} for external changes", settings_path.display());
+
+  let settings_path = settings_path.to_path_buf();
+  tauri::async_runtime::spawn(async move {
+    while receiver.recv().await.is_some() {
+      tokio::time::sleep(SETTLE_DELAY).await;
+      while receiver.try_recv().is_ok() {}
+      reload(&app_handle, &settings_path).await;
+    }
+  });
+
+  Ok(SettingsWatcher { _watcher: watcher })
+}
+
+async fn reload(app_handle: &tauri::AppHandle, settings_path: &Path) {
+  let Some(config) = app_handle.try_state::<tokio::sync::Mutex<LauncherConfig>>() else {
+    return;
+  };
+  // Read while holding the lock so we can't race one of our own writes
+  let mut config_lock = config.lock().await;
+  let contents = match std::fs::read_to_string(settings_path) {
+    Ok(contents) => contents,
+    Err(err) => {
+      tracing::warn!("Unable to read externally changed settings: {err}");
+      return;
+    }
+  };
+
+  match config_lock.reload(&contents) {
+    Ok(true) => tracing::info!("Reloaded settings after an external change"),
+    Ok(false) => {}
+    Err(err) => tracing::warn!("Rejected external change to settings: {err:#}"),
+  }
+}
@@ -18,6 +18,7 @@ use std::io::Write;
 mod cache;
 mod commands;
 mod config;
+mod releases;
 mod util;
 
 static LOG_GUARD: OnceCell<WorkerGuard> = OnceCell::const_new();
@@ -131,15 +132,23 @@ fn main() {
       //
       // This allows us to avoid hacky globals, and pass around information (in this case, the config)
       // to the relevant places
-      let config = tokio::sync::Mutex::new(config::LauncherConfig::load_config(
+      let config = config::LauncherConfig::load_config(
         app
           .path()
           .app_config_dir()
           .expect("Failed to resolve app config directory"),
-      ));
-      app.manage(config);
+      );
+      // Pick up hand edits to the settings file, not being able to is not fatal
+      match config.watch_for_changes(app.app_handle().clone()) {
+        Ok(watcher) => {
+          app.manage(watcher);
+        }
+        Err(err) => tracing::error!("Unable to watch settings for changes: {err:#}"),
+      }
+      app.manage(tokio::sync::Mutex::new(config));
       let cache = tokio::sync::Mutex::new(cache::ModCache::default());
       app.manage(cache);
+      app.manage(tokio::sync::Mutex::new(releases::ReleaseIndex::default()));
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
@@ -157,28 +166,39 @@ fn main() {
       commands::config::cleanup_enabled_texture_packs,
       commands::config::does_active_tooling_version_meet_minimum,
       commands::config::does_active_tooling_version_support_game,
+      commands::config::diagnose_install,
       commands::config::is_avx_requirement_met,
       commands::config::is_opengl_requirement_met,
+      commands::config::list_settings_backups,
+      commands::config::relocate_install_directory,
+      commands::config::repair_install,
       commands::config::reset_to_defaults,
+      commands::config::restore_settings_backup,
       commands::config::set_install_directory,
       commands::config::set_texture_packs,
       commands::config::set_game_installed,
+      commands::config::set_game_pinned_version,
       commands::config::set_active_version,
       commands::config::set_bypass_requirements,
       commands::config::set_locale,
       commands::config::set_auto_update_games,
       commands::config::set_check_for_latest_mod_version,
       commands::config::set_delete_previous_versions,
+      commands::config::set_release_index_url,
+      commands::config::set_versions_to_keep,
       commands::config::set_rip_levels,
       commands::config::set_rip_collision,
       commands::config::set_rip_textures,
       commands::config::set_rip_streamed_audio,
       commands::config::update_mod_sources,
       commands::config::get_launcher_config,
+      commands::config::get_previous_active_version,
+      commands::config::rollback_active_version,
       commands::config::set_hide_beta_alerts,
       commands::config::set_hide_gamename,
       commands::config::set_hide_playtime,
       commands::download::download_file,
+      commands::features::archives::inspect_archive,
       commands::features::mods::base_game_iso_exists,
       commands::features::mods::compile_for_mod_install,
       commands::features::mods::decompile_for_mod_install,
@@ -198,15 +218,28 @@ fn main() {
       commands::features::texture_packs::list_extracted_texture_pack_info,
       commands::features::texture_packs::update_texture_pack_data,
       commands::game::get_furthest_game_milestone,
+      commands::game::list_outdated_games,
       commands::game::reset_game_settings,
       commands::game::uninstall_game,
+      commands::game::update_outdated_games,
+      commands::operations::cancel_operation,
+      commands::profiles::create_install_profile,
+      commands::profiles::delete_install_profile,
+      commands::profiles::list_install_profiles,
+      commands::profiles::switch_install_profile,
       commands::support::generate_support_package,
       commands::util::frontend_log,
       commands::util::is_diskspace_requirement_met,
       commands::util::is_minimum_vcc_runtime_installed,
+      commands::versions::apply_version_retention,
       commands::versions::download_version,
       commands::versions::ensure_active_version_still_exists,
+      commands::versions::get_latest_version,
+      commands::versions::import_local_version,
+      commands::versions::list_available_versions,
       commands::versions::list_downloaded_versions,
+      commands::versions::preview_version_retention,
+      commands::versions::probe_tooling_version,
       commands::versions::remove_version,
     ])
     .build(tauri::generate_context!())
@@ -0,0 +1,219 @@
+// Index of the official tooling releases
+//
+// Releases are read from a GitHub style releases endpoint (a list of release objects, paginated
+// through the `Link` header) and cached for a little while, so moving around the launcher doesn't
+// eat into the API's rate limit.  The endpoint can be pointed at a mirror through the
+// `releaseIndexUrl` setting.
+
+use anyhow::{Context, Result};
+use reqwest::header::{ACCEPT, LINK};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri_plugin_os::platform;
+use ts_rs::TS;
+
+pub const DEFAULT_RELEASE_INDEX_URL: &str =
+  "https://api.github.com/repos/open-goal/jak-project/releases?per_page=100";
+
+const CACHE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+  name: String,
+  browser_download_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+  tag_name: String,
+  published_at: Option<String>,
+  html_url: Option<String>,
+  body: Option<String>,
+  #[serde(default)]
+  draft: bool,
+  #[serde(default)]
+  prerelease: bool,
+  #[serde(default)]
+  assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ReleaseInfo {
+  pub version: String,
+  pub published_at: Option<String>,
+  pub release_url: Option<String>,
+  /// The asset for the current platform, if the release has one
+  pub download_url: Option<String>,
+  pub prerelease: bool,
+  pub invalid: bool,
+  pub invalidation_reasons: Vec<String>,
+  #[serde(skip)]
+  #[ts(skip)]
+  semver: Option<Version>,
+}
+
+fn platform_asset(assets: &[GithubAsset]) -> Option<&GithubAsset> {
+  let platform = platform();
+  let arch = if std::env::consts::ARCH == "aarch64" {
+    "arm"
+  } else {
+    "intel"
+  };
+  assets.iter().find(|asset| {
+    let name = asset.name.to_lowercase();
+    name.contains(platform)
+      && !name.contains(".bin")
+      && !name.contains("lsp")
+      && !name.ends_with(".sha256")
+      // Only the macOS builds are split by architecture
+      && (platform != "macos" || name.contains(arch))
+  })
+}
+
+// Broken releases are flagged in their notes with `<!-- invalid: reason | another reason -->`
+fn invalidation_reasons(body: Option<&str>) -> Option<Vec<String>> {
+  let (_, marker) = body?.split_once("<!-- invalid:")?;
+  let reasons = marker
+    .split_once("-->")
+    .map(|(reasons, _)| {
+      reasons
+        .split('|')
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .map(str::to_owned)
+        .collect()
+    })
+    .unwrap_or_default();
+  Some(reasons)
+}
+
+impl From<GithubRelease> for ReleaseInfo {
+  fn from(release: GithubRelease) -> Self {
+    let semver = Version::parse(release.tag_name.trim_start_matches('v')).ok();
+    let invalidation_reasons = invalidation_reasons(release.body.as_deref());
+    Self {
+      download_url: platform_asset(&release.assets).map(|asset| asset.browser_download_url.clone()),
+      prerelease: release.prerelease || semver.as_ref().is_some_and(|v| !v.pre.is_empty()),
+      invalid: invalidation_reasons.is_some(),
+      invalidation_reasons: invalidation_reasons.unwrap_or_default(),
+      version: release.tag_name,
+      published_at: release.published_at,
+      release_url: release.html_url,
+      semver,
+    }
+  }
+}
+
+// `<https://...&page=2>; rel="next", <https://...&page=5>; rel="last"`
+fn next_page(response: &reqwest::Response) -> Option<String> {
+  let links = response.headers().get(LINK)?.to_str().ok()?;
+  links.split(',').find_map(|link| {
+    let (url, params) = link.split_once(';')?;
+    params
+      .split(';')
+      .any(|param| param.trim().eq_ignore_ascii_case("rel=\"next\""))
+      .then(|| {
+        url
+          .trim()
+          .trim_start_matches('<')
+          .trim_end_matches('>')
+          .to_owned()
+      })
+  })
+}
+
+async fn fetch_releases(endpoint: &str) -> Result<Vec<ReleaseInfo>> {
+  // GitHub rejects requests without a user agent
+  let client = reqwest::Client::builder()
+    .user_agent(concat!("opengoal-launcher/", env!("CARGO_PKG_VERSION")))
+    .build()?;
+
+  let mut releases = Vec::new();
+  let mut page = Some(endpoint.to_owned());
+  while let Some(url) = page {
+    let response = client
+      .get(&url)
+      .header(ACCEPT, "application/vnd.github+json")
+      .send()
+      .await
+      .with_context(|| format!("Failed to fetch releases from {url}"))?
+      .error_for_status()
+      .with_context(|| format!("Server returned error status for {url}"))?;
+    page = next_page(&response);
+    let github_releases: Vec<GithubRelease> = response
+      .json()
+      .await
+      .with_context(|| format!("Failed to parse releases from {url}"))?;
+    releases.extend(
+      github_releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .map(ReleaseInfo::from),
+    );
+  }
+
+  for release in releases.iter().filter(|release| release.semver.is_none()) {
+    tracing::warn!(
+      "Ignoring release with a non-semver tag: {}",
+      release.version
+    );
+  }
+  releases.retain(|release| release.semver.is_some());
+  releases.sort_by(|a, b| b.semver.cmp(&a.semver));
+  Ok(releases)
+}
+
+#[derive(Default)]
+pub struct ReleaseIndex {
+  endpoint: String,
+  fetched_at: Option<Instant>,
+  releases: Vec<ReleaseInfo>,
+}
+
+impl ReleaseIndex {
+  fn is_fresh(&self, endpoint: &str) -> bool {
+    self.endpoint == endpoint
+      && self
+        .fetched_at
+        .is_some_and(|fetched_at| fetched_at.elapsed() < CACHE_LIFETIME)
+  }
+
+  /// Every release published at `endpoint`, newest first.  If the endpoint can't be reached the
+  /// last known releases are used instead.
+  pub async fn releases(&mut self, endpoint: &str, refresh: bool) -> Result<&[ReleaseInfo]> {
+    if refresh || !self.is_fresh(endpoint) {
+      match fetch_releases(endpoint).await {
+        Ok(releases) => {
+          tracing::info!("Fetched {} releases from {endpoint}", releases.len());
+          self.endpoint = endpoint.to_owned();
+          self.fetched_at = Some(Instant::now());
+          self.releases = releases;
+        }
+        Err(err) if self.endpoint == endpoint && self.fetched_at.is_some() => {
+          tracing::warn!("Using previously fetched releases: {err:#}");
+        }
+        Err(err) => return Err(err),
+      }
+    }
+    Ok(&self.releases)
+  }
+
+  /// The newest stable release that can be installed on this platform
+  pub async fn latest_release(
+    &mut self,
+    endpoint: &str,
+    refresh: bool,
+  ) -> Result<Option<ReleaseInfo>> {
+    Ok(
+      self
+        .releases(endpoint, refresh)
+        .await?
+        .iter()
+        .find(|release| !release.prerelease && !release.invalid && release.download_url.is_some())
+        .cloned(),
+    )
+  }
+}
@@ -1,10 +1,15 @@
+pub mod activation;
+pub mod archive;
+pub mod cancellation;
 pub mod file;
 pub mod game_milestones;
 pub mod game_tests;
 pub mod network;
 pub mod os;
 pub mod process;
+pub mod progress;
 pub mod tar;
+pub mod tooling;
 pub mod zip;
 
 use crate::TAURI_APP;
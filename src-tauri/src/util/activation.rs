@@ -0,0 +1,325 @@
+// Puts a version's files into `active/` without ever leaving a mix of two versions behind
+//
+// The files are first copied into `<dir>.staging` next to the live directory, which is the slow
+// part but can't break anything.  The live directory is then renamed to `<dir>.replaced` and the
+// staged one takes its place, so nothing of the version it replaces is left behind.  Whatever the
+// launcher put there that isn't part of a version (ie. the files extracted from the game's ISO) is
+// moved over into the new live directory.  If any of those renames fail the ones done so far are
+// undone.  The version that got replaced is kept in `<dir>.previous`, so it can be put back just as
+// cheaply.
+//
+// Every installed set is tagged with the version it came from and lists the files it installed,
+// reinstalling the same version doesn't throw away the backup of the one before it.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::file::{create_dir, delete_dir};
+
+const VERSION_MARKER: &str = ".launcher-version";
+const FILE_LIST: &str = ".launcher-files";
+
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+  let mut path = dir.as_os_str().to_owned();
+  path.push(".");
+  path.push(suffix);
+  PathBuf::from(path)
+}
+
+fn version_label(dir: &Path) -> Option<String> {
+  std::fs::read_to_string(dir.join(VERSION_MARKER))
+    .ok()
+    .map(|label| label.trim().to_owned())
+    .filter(|label| !label.is_empty())
+}
+
+/// The version the files that would be restored by `restore_previous` came from
+pub fn previous_version_label(dir: &Path) -> Option<String> {
+  version_label(&sibling(dir, "previous"))
+}
+
+// Every file under `dir`, relative to it
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  for entry in WalkDir::new(dir).min_depth(1) {
+    let entry = entry?;
+    if !entry.file_type().is_dir() {
+      files.push(entry.path().strip_prefix(dir)?.to_path_buf());
+    }
+  }
+  Ok(files)
+}
+
+// The files a version installed into `dir`, `None` if it was installed before these were listed
+fn installed_files(dir: &Path) -> Option<Vec<PathBuf>> {
+  let contents = std::fs::read_to_string(dir.join(FILE_LIST)).ok()?;
+  Some(contents.lines().map(PathBuf::from).collect())
+}
+
+/// What in a live directory belongs to the version installed there
+enum Owned {
+  Everything,
+  Files {
+    files: HashSet<PathBuf>,
+    // Every folder leading up to one of the files
+    dirs: HashSet<PathBuf>,
+  },
+}
+
+impl Owned {
+  fn files(files: Vec<PathBuf>) -> Self {
+    let dirs = files
+      .iter()
+      .flat_map(|file| file.ancestors().skip(1))
+      .map(Path::to_path_buf)
+      .collect();
+    let mut files: HashSet<PathBuf> = files.into_iter().collect();
+    files.extend([PathBuf::from(VERSION_MARKER), PathBuf::from(FILE_LIST)]);
+    Self::Files { files, dirs }
+  }
+
+  // `Everything` if the directory has to be taken as a whole
+  fn of(
+    dir: &Path,
+    whole_dir: bool,
+    fallback: impl FnOnce() -> Result<Vec<PathBuf>>,
+  ) -> Result<Self> {
+    match installed_files(dir) {
+      Some(files) => Ok(Self::files(files)),
+      None if whole_dir => Ok(Self::Everything),
+      // Installed by an older launcher, the best guess is what the other version has
+      None => Ok(Self::files(fallback()?)),
+    }
+  }
+
+  // The files and folders under `dir` that no version put there, folders are only listed if
+  // nothing under them belongs to one.  Anything `live` already has stays where it is.
+  fn foreign_entries(&self, dir: &Path, live: &Path) -> Result<Vec<PathBuf>> {
+    let Self::Files { files, dirs } = self else {
+      return Ok(Vec::new());
+    };
+    let mut foreign = Vec::new();
+    let mut entries = WalkDir::new(dir).min_depth(1).into_iter();
+    while let Some(entry) = entries.next() {
+      let entry = entry?;
+      let relative_path = entry.path().strip_prefix(dir)?;
+      let is_dir = entry.file_type().is_dir();
+      if is_dir && (dirs.contains(relative_path) || live.join(relative_path).is_dir()) {
+        continue;
+      }
+      if is_dir {
+        entries.skip_current_dir();
+      }
+      if !files.contains(relative_path) && live.join(relative_path).symlink_metadata().is_err() {
+        foreign.push(relative_path.to_path_buf());
+      }
+    }
+    Ok(foreign)
+  }
+}
+
+#[derive(Default)]
+struct Journal {
+  moves: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Journal {
+  fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+      create_dir(parent)?;
+    }
+    std::fs::rename(from, to)
+      .with_context(|| format!("Unable to move {} to {}", from.display(), to.display()))?;
+    self.moves.push((from.to_path_buf(), to.to_path_buf()));
+    Ok(())
+  }
+
+  fn undo(self) {
+    for (from, to) in self.moves.into_iter().rev() {
+      if let Err(err) = std::fs::rename(&to, &from) {
+        tracing::error!(
+          "Unable to move {} back to {}: {err}",
+          to.display(),
+          from.display()
+        );
+      }
+    }
+  }
+}
+
+// Puts `incoming` in place of `live`, which becomes `outgoing`.  Whatever in it isn't `owned` by
+// the outgoing version is moved into the new `live`.  Either everything is moved, or nothing is.
+fn swap_dirs(incoming: &Path, live: &Path, outgoing: &Path, owned: &Owned) -> Result<()> {
+  let mut journal = Journal::default();
+  let mut swap = || -> Result<()> {
+    let has_live = live.exists();
+    if has_live {
+      journal.rename(live, outgoing)?;
+    }
+    journal.rename(incoming, live)?;
+    if has_live {
+      for relative_path in owned.foreign_entries(outgoing, live)? {
+        journal.rename(&outgoing.join(&relative_path), &live.join(&relative_path))?;
+      }
+    }
+    Ok(())
+  };
+  let result = swap();
+  if result.is_err() {
+    journal.undo();
+  }
+  result
+}
+
+// Keeps what `swap_dirs` moved out of `live` as its previous version, or throws it away.  By now
+// the new files are live, so this failing is only logged: reporting it as an error would have the
+// config go back to a version whose files aren't there anymore.
+fn put_away_replaced(replaced: &Path, previous: &Path, keep: bool, live: &Path) {
+  let put_away = || -> Result<()> {
+    if keep {
+      delete_dir(previous)?;
+      std::fs::rename(replaced, previous)
+        .with_context(|| format!("Unable to move {} aside", replaced.display()))?;
+    } else {
+      delete_dir(replaced)?;
+    }
+    Ok(())
+  };
+  if let Err(err) = put_away() {
+    tracing::warn!(
+      "Unable to clean up after swapping {}: {err:#}",
+      live.display()
+    );
+  }
+}
+
+/// Replaces `live` with `files` from `src_dir`, labelled as `version`.
+///
+/// With `files`, `live` is taken to only ever hold the version's files and is replaced as a whole.
+/// Otherwise all of `src_dir` is installed, and whatever the previous version didn't install in
+/// `live` is kept.
+pub fn install(src_dir: &Path, files: Option<&[String]>, live: &Path, version: &str) -> Result<()> {
+  let staging = sibling(live, "staging");
+  let replaced = sibling(live, "replaced");
+  let previous = sibling(live, "previous");
+  delete_dir(&staging)?;
+  delete_dir(&replaced)?;
+
+  create_dir(&staging)?;
+  match files {
+    Some(files) => {
+      for file in files {
+        std::fs::copy(src_dir.join(file), staging.join(file))
+          .with_context(|| format!("Unable to stage {}", src_dir.join(file).display()))?;
+      }
+    }
+    None => {
+      let options = fs_extra::dir::CopyOptions::new().content_only(true);
+      fs_extra::dir::copy(src_dir, &staging, &options)
+        .with_context(|| format!("Unable to stage {}", src_dir.display()))?;
+    }
+  }
+  let staged_files = list_files(&staging)?;
+  let file_list: Vec<String> = staged_files
+    .iter()
+    .map(|file| file.to_string_lossy().into_owned())
+    .collect();
+  std::fs::write(staging.join(FILE_LIST), file_list.join("\n"))?;
+  std::fs::write(staging.join(VERSION_MARKER), version)?;
+
+  let owned = Owned::of(live, files.is_some(), || Ok(staged_files))?;
+  let replaces_other_version = version_label(live).is_some_and(|label| label != version);
+  if let Err(err) = swap_dirs(&staging, live, &replaced, &owned) {
+    let _ = delete_dir(&staging);
+    return Err(err);
+  }
+
+  put_away_replaced(&replaced, &previous, replaces_other_version, live);
+  Ok(())
+}
+
+/// Swaps the version replaced by the last `install` of another version back in, returning the
+/// version it belongs to.  What it replaces becomes the new previous version.
+pub fn restore_previous(live: &Path) -> Result<String> {
+  let previous = sibling(live, "previous");
+  let replaced = sibling(live, "replaced");
+  let version = version_label(&previous)
+    .with_context(|| format!("Nothing to restore for {}", live.display()))?;
+
+  delete_dir(&replaced)?;
+  let owned = Owned::of(live, false, || list_files(&previous))?;
+  swap_dirs(&previous, live, &replaced, &owned)?;
+  put_away_replaced(&replaced, &previous, true, live);
+  Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn version_dir(root: &Path, version: &str) -> PathBuf {
+    let dir = root.join(version);
+    create_dir(&dir).unwrap();
+    std::fs::write(dir.join("code.gc"), version).unwrap();
+    dir
+  }
+
+  fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+  }
+
+  #[test]
+  fn keeps_files_that_no_version_installed() {
+    let root = tempfile::tempdir().unwrap();
+    let live = root.path().join("data");
+    install(&version_dir(root.path(), "v1"), None, &live, "official/v1").unwrap();
+    create_dir(&live.join("iso_data")).unwrap();
+    std::fs::write(live.join("iso_data").join("game.dgo"), "iso").unwrap();
+
+    install(&version_dir(root.path(), "v2"), None, &live, "official/v2").unwrap();
+    assert_eq!(read(&live.join("code.gc")), "v2");
+    assert_eq!(read(&live.join("iso_data").join("game.dgo")), "iso");
+    assert_eq!(
+      previous_version_label(&live).as_deref(),
+      Some("official/v1")
+    );
+
+    assert_eq!(restore_previous(&live).unwrap(), "official/v1");
+    assert_eq!(read(&live.join("code.gc")), "v1");
+    assert_eq!(read(&live.join("iso_data").join("game.dgo")), "iso");
+    assert_eq!(
+      previous_version_label(&live).as_deref(),
+      Some("official/v2")
+    );
+  }
+
+  #[test]
+  fn cleanup_failures_dont_fail_the_swap() {
+    let root = tempfile::tempdir().unwrap();
+    let live = root.path().join("bin");
+    let files = ["code.gc".to_owned()];
+    install(
+      &version_dir(root.path(), "v1"),
+      Some(&files),
+      &live,
+      "official/v1",
+    )
+    .unwrap();
+    // The replaced version can't be kept when something that isn't a folder is in the way
+    std::fs::write(sibling(&live, "previous"), "in the way").unwrap();
+
+    // `LauncherConfig::activate_version` keeps the new version in the config as long as this
+    // succeeds, so the files have to be that version's too
+    install(
+      &version_dir(root.path(), "v2"),
+      Some(&files),
+      &live,
+      "official/v2",
+    )
+    .unwrap();
+    assert_eq!(read(&live.join(VERSION_MARKER)), "official/v2");
+    assert_eq!(read(&live.join("code.gc")), "v2");
+  }
+}
@@ -0,0 +1,573 @@
+// The checks every archive goes through while being extracted, whatever its format
+//
+// Mod bundles and texture packs come from third parties, so nothing in them is trusted: entry
+// paths have to stay inside the destination, links can't point out of it or through one another,
+// special files (devices, fifos) are refused and the archive as a whole has to stay within
+// `ExtractionLimits`.  Nothing is ever written through a link extracted earlier, so a harmless
+// looking link can't be used as a stepping stone out of the destination either.
+//
+// The format is told by the first few bytes of the file, names (and extensions) of downloads
+// can't be relied on.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use super::file::create_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub enum ArchiveFormat {
+  #[serde(rename = "zip")]
+  Zip,
+  #[serde(rename = "tar")]
+  Tar,
+  #[serde(rename = "tar.gz")]
+  TarGz,
+  #[serde(rename = "tar.xz")]
+  TarXz,
+  #[serde(rename = "tar.zst")]
+  TarZst,
+}
+
+impl ArchiveFormat {
+  pub fn detect(path: &Path) -> Result<Self> {
+    let mut header = Vec::with_capacity(262);
+    std::fs::File::open(path)
+      .with_context(|| format!("Unable to open {}", path.display()))?
+      .take(262)
+      .read_to_end(&mut header)?;
+
+    let format = if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+      Self::Zip
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+      Self::TarGz
+    } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+      Self::TarXz
+    } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+      Self::TarZst
+    } else if header.get(257..262) == Some(b"ustar") {
+      Self::Tar
+    } else {
+      anyhow::bail!(
+        "Unsupported archive format for {} (expected zip, tar, .tar.gz, .tar.xz or .tar.zst)",
+        path.display()
+      );
+    };
+    Ok(format)
+  }
+}
+
+/// Why an archive was refused
+#[derive(Debug, thiserror::Error)]
+pub enum ExtractionError {
+  #[error("Archive entry '{0}' has an absolute path")]
+  AbsolutePath(String),
+  #[error("Archive entry '{0}' points outside of the destination")]
+  PathTraversal(String),
+  #[error("Archive entry '{entry}' links outside of the destination ({target})")]
+  LinkEscapes { entry: String, target: String },
+  #[error("Archive entry '{0}' would be written through a link")]
+  WritesThroughLink(String),
+  #[error("Archive entry '{0}' is a device or other special file")]
+  SpecialFile(String),
+  #[error("Archive has more than {0} entries")]
+  TooManyEntries(u64),
+  #[error("Archive unpacks to more than {0} bytes")]
+  TooLarge(u64),
+}
+
+pub struct ExtractionLimits {
+  pub max_entries: u64,
+  pub max_total_bytes: u64,
+}
+
+impl Default for ExtractionLimits {
+  fn default() -> Self {
+    Self {
+      max_entries: 250_000,
+      max_total_bytes: 32 * 1024 * 1024 * 1024, // 32 GB
+    }
+  }
+}
+
+fn split_entry_name(name: &str) -> impl Iterator<Item = &str> {
+  // Archives made on Windows sometimes use backslashes
+  name
+    .split(['/', '\\'])
+    .filter(|part| !part.is_empty() && *part != ".")
+}
+
+/// The relative path an archive entry would be extracted to, if it's a safe one
+pub fn sanitize_entry_path(name: &str) -> Result<PathBuf, ExtractionError> {
+  let has_drive = name
+    .split(['/', '\\'])
+    .next()
+    .is_some_and(|first| first.contains(':'));
+  if name.starts_with(['/', '\\']) || has_drive || Path::new(name).is_absolute() {
+    return Err(ExtractionError::AbsolutePath(name.to_owned()));
+  }
+
+  let mut path = PathBuf::new();
+  for part in split_entry_name(name) {
+    if part == ".." {
+      return Err(ExtractionError::PathTraversal(name.to_owned()));
+    }
+    path.push(part);
+  }
+  Ok(path)
+}
+
+/// An entry of an archive as it would be extracted, paths are always separated by `/`
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ArchiveEntry {
+  pub path: String,
+  pub size: u64,
+  pub is_dir: bool,
+}
+
+impl ArchiveEntry {
+  /// `None` for entries that are the archive's root, like `./`
+  pub fn new(name: &str, size: u64, is_dir: bool) -> Result<Option<Self>, ExtractionError> {
+    sanitize_entry_path(name)?;
+    let path = split_entry_name(name).collect::<Vec<_>>().join("/");
+    Ok((!path.is_empty()).then_some(Self { path, size, is_dir }))
+  }
+}
+
+/// The folder every entry is in, if there is exactly one
+pub fn common_top_dir<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+  let mut top_dir: Option<PathBuf> = None;
+  let mut has_nested_entries = false;
+  for path in paths {
+    let mut components = path.components();
+    let Some(first) = components.next() else {
+      continue;
+    };
+    match &top_dir {
+      Some(top_dir) if top_dir.as_os_str() != first.as_os_str() => return None,
+      Some(_) => {}
+      None => top_dir = Some(PathBuf::from(first.as_os_str())),
+    }
+    has_nested_entries |= components.next().is_some();
+  }
+  top_dir.filter(|_| has_nested_entries)
+}
+
+// The folders a link at `entry` pointing to `target` goes through on its way there, relative to
+// the destination.  `None` if it ends up outside of it, going by the paths alone.
+fn link_traversal(entry: &Path, target: &str) -> Option<Vec<PathBuf>> {
+  if target.starts_with(['/', '\\']) || Path::new(target).is_absolute() {
+    return None;
+  }
+  let mut resolved = entry.parent().map(Path::to_path_buf).unwrap_or_default();
+  let mut traversed = Vec::new();
+  for part in split_entry_name(target) {
+    if !resolved.as_os_str().is_empty() {
+      traversed.push(resolved.clone());
+    }
+    if part == ".." {
+      if !resolved.pop() {
+        return None;
+      }
+    } else {
+      resolved.push(part);
+    }
+  }
+  Some(traversed)
+}
+
+/// Writes the entries of one archive into `dest`, refusing anything unsafe
+pub struct SafeExtractor {
+  dest: PathBuf,
+  limits: ExtractionLimits,
+  entries: u64,
+  total_bytes: u64,
+  // The links extracted so far and the folders their targets go through.  Going by the paths
+  // alone only holds up as long as no link goes through another one, `sub -> .` makes `sub/..`
+  // the destination's parent.
+  links: HashSet<PathBuf>,
+  traversed: HashSet<PathBuf>,
+}
+
+impl SafeExtractor {
+  pub fn new(dest: &Path, limits: ExtractionLimits) -> Self {
+    Self {
+      dest: dest.to_path_buf(),
+      limits,
+      entries: 0,
+      total_bytes: 0,
+      links: HashSet::new(),
+      traversed: HashSet::new(),
+    }
+  }
+
+  /// Counts the entry `name` towards the limits and returns its path relative to the
+  /// destination, without `strip_dir`.  `None` if nothing is left of it.
+  pub fn entry_path(
+    &mut self,
+    name: &str,
+    strip_dir: Option<&Path>,
+  ) -> Result<Option<PathBuf>, ExtractionError> {
+    self.entries += 1;
+    if self.entries > self.limits.max_entries {
+      return Err(ExtractionError::TooManyEntries(self.limits.max_entries));
+    }
+    let path = sanitize_entry_path(name)?;
+    let path = match strip_dir {
+      Some(strip_dir) => path.strip_prefix(strip_dir).unwrap_or(&path).to_path_buf(),
+      None => path,
+    };
+    Ok((!path.as_os_str().is_empty()).then_some(path))
+  }
+
+  // Where `relative_path` is, as long as none of the folders leading up to it are links
+  fn target(&self, relative_path: &Path) -> Result<PathBuf> {
+    let mut target = self.dest.clone();
+    let mut components = relative_path.components().peekable();
+    while let Some(component) = components.next() {
+      target.push(component);
+      if components.peek().is_some()
+        && target
+          .symlink_metadata()
+          .is_ok_and(|metadata| metadata.is_symlink())
+      {
+        Err(ExtractionError::WritesThroughLink(
+          relative_path.display().to_string(),
+        ))?;
+      }
+    }
+    Ok(target)
+  }
+
+  // Like `target`, but clears the way for a new entry.  Whatever file or link is already there is
+  // removed rather than written through, it might be a link to somewhere else.
+  fn new_target(&self, relative_path: &Path) -> Result<PathBuf> {
+    let target = self.target(relative_path)?;
+    if target
+      .symlink_metadata()
+      .is_ok_and(|metadata| !metadata.is_dir())
+    {
+      std::fs::remove_file(&target)
+        .with_context(|| format!("Unable to replace {}", target.display()))?;
+    }
+    if let Some(parent) = target.parent() {
+      create_dir(parent)?;
+    }
+    Ok(target)
+  }
+
+  pub fn create_dir(&mut self, relative_path: &Path) -> Result<()> {
+    create_dir(&self.new_target(relative_path)?)
+  }
+
+  /// Writes `contents` to `relative_path`, applying the permission bits of `mode` if given
+  pub fn write_file(
+    &mut self,
+    relative_path: &Path,
+    contents: &mut impl Read,
+    mode: Option<u32>,
+  ) -> Result<()> {
+    let target = self.new_target(relative_path)?;
+    let mut file = std::fs::File::create(&target)
+      .with_context(|| format!("Unable to create {}", target.display()))?;
+
+    let remaining = self.limits.max_total_bytes - self.total_bytes;
+    let written = std::io::copy(&mut contents.take(remaining + 1), &mut file)
+      .with_context(|| format!("Unable to extract {}", target.display()))?;
+    if written > remaining {
+      drop(file);
+      let _ = std::fs::remove_file(&target);
+      Err(ExtractionError::TooLarge(self.limits.max_total_bytes))?;
+    }
+    self.total_bytes += written;
+
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+      use std::os::unix::fs::PermissionsExt;
+      std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode & 0o777))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+  }
+
+  pub fn create_symlink(&mut self, relative_path: &Path, link_target: &str) -> Result<()> {
+    let traversed = link_traversal(relative_path, link_target).filter(|traversed| {
+      !self.traversed.contains(relative_path)
+        && !traversed
+          .iter()
+          .any(|dir| self.links.contains(dir) || self.dest.join(dir).is_symlink())
+    });
+    let Some(traversed) = traversed else {
+      Err(ExtractionError::LinkEscapes {
+        entry: relative_path.display().to_string(),
+        target: link_target.to_owned(),
+      })?
+    };
+    let target = self.new_target(relative_path)?;
+    self.links.insert(relative_path.to_path_buf());
+    self.traversed.extend(traversed);
+    #[cfg(unix)]
+    {
+      std::os::unix::fs::symlink(link_target, &target)
+        .with_context(|| format!("Unable to create link {}", target.display()))?;
+    }
+    #[cfg(not(unix))]
+    tracing::warn!("Skipping link {} in archive", relative_path.display());
+    Ok(())
+  }
+
+  /// Links `relative_path` to the earlier entry `link_name`
+  pub fn create_hard_link(&mut self, relative_path: &Path, link_name: &str) -> Result<()> {
+    let original = self.target(&sanitize_entry_path(link_name)?)?;
+    if !original
+      .symlink_metadata()
+      .is_ok_and(|metadata| metadata.is_file())
+    {
+      Err(ExtractionError::LinkEscapes {
+        entry: relative_path.display().to_string(),
+        target: link_name.to_owned(),
+      })?;
+    }
+    let target = self.new_target(relative_path)?;
+    std::fs::hard_link(&original, &target)
+      .with_context(|| format!("Unable to create link {}", target.display()))?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{Cursor, Write};
+  use tar::EntryType;
+  use zip::write::SimpleFileOptions;
+
+  fn limits() -> ExtractionLimits {
+    ExtractionLimits {
+      max_entries: 8,
+      max_total_bytes: 1024,
+    }
+  }
+
+  // `tar::Builder` won't write unsafe paths itself, so the names go into the header as they are
+  fn tar_archive(entries: &[(&str, EntryType, &str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, entry_type, link_name, data) in entries {
+      let mut header = tar::Header::new_gnu();
+      header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+      header.set_link_name_literal(link_name).unwrap();
+      header.set_entry_type(*entry_type);
+      header.set_mode(0o644);
+      header.set_size(data.len() as u64);
+      header.set_cksum();
+      builder.append(&header, *data).unwrap();
+    }
+    builder.into_inner().unwrap()
+  }
+
+  fn file(name: &str) -> (&str, EntryType, &str, &[u8]) {
+    (name, EntryType::Regular, "", b"contents")
+  }
+
+  fn symlink<'a>(name: &'a str, target: &'a str) -> (&'a str, EntryType, &'a str, &'a [u8]) {
+    (name, EntryType::Symlink, target, b"")
+  }
+
+  fn unpack_tar(entries: &[(&str, EntryType, &str, &[u8])]) -> (tempfile::TempDir, Result<()>) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut archive = tar::Archive::new(Cursor::new(tar_archive(entries)));
+    let result = crate::util::tar::unpack(&mut archive, &dir.path().join("dest"), None, limits());
+    (dir, result)
+  }
+
+  fn unpack_zip(build: impl FnOnce(&mut zip::ZipWriter<Cursor<Vec<u8>>>)) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    build(&mut writer);
+    let mut zip = zip::ZipArchive::new(writer.finish().unwrap()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    crate::util::zip::unpack(&mut zip, &dir.path().join("dest"), None, limits())
+  }
+
+  fn refused(result: Result<()>) -> ExtractionError {
+    result
+      .expect_err("archive should have been refused")
+      .downcast()
+      .expect("archive should have been refused as unsafe")
+  }
+
+  // Links are skipped on other platforms
+  #[cfg(unix)]
+  #[test]
+  fn extracts_safe_tar() {
+    let (dir, result) = unpack_tar(&[
+      ("dir/", EntryType::Directory, "", b""),
+      file("dir/file"),
+      symlink("dir/link", "../other"),
+      file("other"),
+      ("hard", EntryType::Link, "dir/file", b""),
+    ]);
+    result.unwrap();
+    let dest = dir.path().join("dest");
+    assert_eq!(std::fs::read(dest.join("dir/file")).unwrap(), b"contents");
+    assert_eq!(std::fs::read(dest.join("dir/link")).unwrap(), b"contents");
+    assert_eq!(std::fs::read(dest.join("hard")).unwrap(), b"contents");
+  }
+
+  #[test]
+  fn refuses_path_traversal() {
+    for name in ["../evil", "dir/../../evil", "..\\evil"] {
+      let (dir, result) = unpack_tar(&[file(name)]);
+      assert!(matches!(refused(result), ExtractionError::PathTraversal(_)));
+      assert!(!dir.path().join("evil").exists());
+    }
+  }
+
+  #[test]
+  fn refuses_absolute_paths() {
+    for name in ["/evil", "\\evil", "C:/evil", "C:\\evil", "C:evil"] {
+      let (_dir, result) = unpack_tar(&[file(name)]);
+      assert!(matches!(refused(result), ExtractionError::AbsolutePath(_)));
+    }
+  }
+
+  #[test]
+  fn refuses_escaping_symlinks() {
+    for target in ["..", "../evil", "dir/../../evil", "/etc", "\\etc"] {
+      let (_dir, result) = unpack_tar(&[symlink("link", target)]);
+      assert!(matches!(
+        refused(result),
+        ExtractionError::LinkEscapes { .. }
+      ));
+    }
+  }
+
+  #[test]
+  fn refuses_symlinks_going_through_other_links() {
+    // `sub` is the destination itself, so `sub/..` is its parent
+    let (_dir, result) = unpack_tar(&[symlink("sub", "."), symlink("x", "sub/..")]);
+    assert!(matches!(
+      refused(result),
+      ExtractionError::LinkEscapes { .. }
+    ));
+    let (dir, result) = unpack_tar(&[symlink("x", "sub/.."), symlink("sub", ".")]);
+    assert!(matches!(
+      refused(result),
+      ExtractionError::LinkEscapes { .. }
+    ));
+    assert!(!dir.path().join("dest/sub").exists());
+  }
+
+  #[test]
+  fn refuses_escaping_hard_links() {
+    let (_dir, result) = unpack_tar(&[("hard", EntryType::Link, "../evil", b"")]);
+    assert!(matches!(refused(result), ExtractionError::PathTraversal(_)));
+    let (_dir, result) = unpack_tar(&[("hard", EntryType::Link, "/etc/passwd", b"")]);
+    assert!(matches!(refused(result), ExtractionError::AbsolutePath(_)));
+    // Linking to a symlink would link to whatever it points at
+    let (_dir, result) = unpack_tar(&[
+      symlink("link", "other"),
+      ("hard", EntryType::Link, "link", b""),
+    ]);
+    assert!(matches!(
+      refused(result),
+      ExtractionError::LinkEscapes { .. }
+    ));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn refuses_writing_through_links() {
+    let (dir, result) = unpack_tar(&[
+      ("real/", EntryType::Directory, "", b""),
+      symlink("link", "real"),
+      file("link/file"),
+    ]);
+    assert!(matches!(
+      refused(result),
+      ExtractionError::WritesThroughLink(_)
+    ));
+    assert!(!dir.path().join("dest/real/file").exists());
+  }
+
+  #[test]
+  fn refuses_special_files() {
+    for entry_type in [EntryType::Char, EntryType::Block, EntryType::Fifo] {
+      let (_dir, result) = unpack_tar(&[("special", entry_type, "", b"")]);
+      assert!(matches!(refused(result), ExtractionError::SpecialFile(_)));
+    }
+  }
+
+  #[test]
+  fn refuses_too_many_entries() {
+    let names: Vec<String> = (0..9).map(|i| format!("file{i}")).collect();
+    let entries: Vec<_> = names.iter().map(|name| file(name)).collect();
+    let (_dir, result) = unpack_tar(&entries);
+    assert!(matches!(
+      refused(result),
+      ExtractionError::TooManyEntries(8)
+    ));
+  }
+
+  #[test]
+  fn refuses_too_many_bytes() {
+    let contents = [0u8; 600];
+    let (dir, result) = unpack_tar(&[
+      ("first", EntryType::Regular, "", &contents),
+      ("second", EntryType::Regular, "", &contents),
+    ]);
+    assert!(matches!(refused(result), ExtractionError::TooLarge(1024)));
+    assert!(!dir.path().join("dest/second").exists());
+  }
+
+  #[test]
+  fn refuses_unsafe_zip_entries() {
+    let options = SimpleFileOptions::default();
+    for name in ["../evil", "dir/../../evil"] {
+      let result = unpack_zip(|zip| zip.start_file(name, options).unwrap());
+      assert!(matches!(refused(result), ExtractionError::PathTraversal(_)));
+    }
+    for name in ["/evil", "C:\\evil"] {
+      let result = unpack_zip(|zip| zip.start_file(name, options).unwrap());
+      assert!(matches!(refused(result), ExtractionError::AbsolutePath(_)));
+    }
+    let result = unpack_zip(|zip| zip.add_symlink("link", "../evil", options).unwrap());
+    assert!(matches!(
+      refused(result),
+      ExtractionError::LinkEscapes { .. }
+    ));
+    let result = unpack_zip(|zip| {
+      zip.add_symlink("sub", ".", options).unwrap();
+      zip.add_symlink("x", "sub/..", options).unwrap();
+    });
+    assert!(matches!(
+      refused(result),
+      ExtractionError::LinkEscapes { .. }
+    ));
+  }
+
+  #[test]
+  fn refuses_oversized_zips() {
+    let options = SimpleFileOptions::default();
+    let result = unpack_zip(|zip| {
+      for i in 0..9 {
+        zip.start_file(format!("file{i}"), options).unwrap();
+      }
+    });
+    assert!(matches!(
+      refused(result),
+      ExtractionError::TooManyEntries(8)
+    ));
+    let result = unpack_zip(|zip| {
+      zip.start_file("big", options).unwrap();
+      zip.write_all(&[0u8; 2048]).unwrap();
+    });
+    assert!(matches!(refused(result), ExtractionError::TooLarge(1024)));
+  }
+}
@@ -0,0 +1,83 @@
+// Registry of the long running operations that can currently be cancelled, keyed by the same
+// operation id used for progress events
+//
+// Commands register their operation for as long as they run, the lower level helpers (downloads,
+// extraction, `watch_process`) look up the token for the operation id they were given.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tokio_util::sync::CancellationToken;
+
+static OPERATIONS: LazyLock<Mutex<HashMap<String, (u64, CancellationToken)>>> =
+  LazyLock::new(Default::default);
+static NEXT_REGISTRATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, thiserror::Error)]
+#[error("Operation '{0}' was cancelled")]
+pub struct OperationCancelled(pub String);
+
+/// Keeps an operation cancellable until dropped
+pub struct OperationGuard {
+  operation_id: String,
+  registration: u64,
+}
+
+impl Drop for OperationGuard {
+  fn drop(&mut self) {
+    let mut operations = OPERATIONS.lock().unwrap_or_else(|err| err.into_inner());
+    // Only if it hasn't been replaced by a newer operation with the same id
+    if operations
+      .get(&self.operation_id)
+      .is_some_and(|(registration, _)| *registration == self.registration)
+    {
+      operations.remove(&self.operation_id);
+    }
+  }
+}
+
+pub fn register(operation_id: &str) -> OperationGuard {
+  let registration = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+  let previous = OPERATIONS
+    .lock()
+    .unwrap_or_else(|err| err.into_inner())
+    .insert(
+      operation_id.to_owned(),
+      (registration, CancellationToken::new()),
+    );
+  if previous.is_some() {
+    tracing::warn!("Operation '{operation_id}' was started again while still running");
+  }
+  OperationGuard {
+    operation_id: operation_id.to_owned(),
+    registration,
+  }
+}
+
+pub fn token(operation_id: &str) -> Option<CancellationToken> {
+  OPERATIONS
+    .lock()
+    .unwrap_or_else(|err| err.into_inner())
+    .get(operation_id)
+    .map(|(_, token)| token.clone())
+}
+
+/// Returns false if there is no such operation running
+pub fn cancel(operation_id: &str) -> bool {
+  match token(operation_id) {
+    Some(token) => {
+      tracing::info!("Cancelling operation '{operation_id}'");
+      token.cancel();
+      true
+    }
+    None => false,
+  }
+}
+
+/// Resolves once the operation is cancelled, never if it can't be
+pub async fn cancelled(token: Option<&CancellationToken>) {
+  match token {
+    Some(token) => token.cancelled().await,
+    None => std::future::pending().await,
+  }
+}
@@ -3,7 +3,14 @@ extern crate rustc_serialize;
 use anyhow::{Context, Result};
 use rustc_serialize::base64::{MIME, ToBase64};
 use rustc_serialize::hex::ToHex;
-use std::{fs::File, io::Read, path::Path};
+use sha2::{Digest, Sha256};
+use std::{
+  collections::BTreeMap,
+  fs::File,
+  io::{Read, Write},
+  path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
 
 pub fn delete_dir(path: impl AsRef<Path>) -> Result<()> {
   let path = path.as_ref();
@@ -38,6 +45,23 @@ pub fn overwrite_dir(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<()>
   Ok(())
 }
 
+/// Returns true if every file under `src` exists under `dst` with the same size, and vice versa
+pub fn dir_contents_match(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<bool> {
+  fn file_sizes(root: &Path) -> Result<BTreeMap<PathBuf, u64>> {
+    let mut sizes = BTreeMap::new();
+    for entry in WalkDir::new(root) {
+      let entry = entry.with_context(|| format!("Unable to read directory: {}", root.display()))?;
+      if entry.file_type().is_file() {
+        let relative_path = entry.path().strip_prefix(root)?.to_path_buf();
+        sizes.insert(relative_path, entry.metadata()?.len());
+      }
+    }
+    Ok(sizes)
+  }
+
+  Ok(file_sizes(src.as_ref())? == file_sizes(dst.as_ref())?)
+}
+
 pub fn touch_file(path: impl AsRef<Path>) -> Result<()> {
   let path = path.as_ref();
   std::fs::OpenOptions::new()
@@ -49,6 +73,89 @@ pub fn touch_file(path: impl AsRef<Path>) -> Result<()> {
   Ok(())
 }
 
+/// Writes `contents` to a temporary file next to `path`, flushes it to disk and then renames it
+/// over `path`.  Readers will either see the old file or the new one, never a partial write.
+pub fn write_file_atomic(path: impl AsRef<Path>, contents: &[u8]) -> Result<()> {
+  let path = path.as_ref();
+  let parent = path
+    .parent()
+    .with_context(|| format!("No parent directory for: {}", path.display()))?;
+  create_dir(parent)?;
+
+  let mut temp_file = tempfile::NamedTempFile::new_in(parent)
+    .with_context(|| format!("Failed to create temp file in: {}", parent.display()))?;
+  temp_file.write_all(contents)?;
+  temp_file
+    .as_file()
+    .sync_all()
+    .with_context(|| format!("Failed to flush temp file for: {}", path.display()))?;
+  temp_file
+    .persist(path)
+    .with_context(|| format!("Failed to replace file: {}", path.display()))?;
+
+  // Make sure the rename itself survives a crash, not supported on windows
+  #[cfg(unix)]
+  if let Ok(dir) = File::open(parent) {
+    let _ = dir.sync_all();
+  }
+  Ok(())
+}
+
+/// A temporary folder under `install_dir` to put an install together in, before `replace_dir`
+/// swaps it into place.  It's removed when dropped, along with anything left in it.
+pub fn staging_dir(install_dir: &Path, prefix: &str) -> Result<tempfile::TempDir> {
+  // Under `features` so it's on the same drive as what it replaces, renames can't cross drives
+  let parent = install_dir.join("features").join(".staging");
+  create_dir(&parent)?;
+  tempfile::Builder::new()
+    .prefix(prefix)
+    .tempdir_in(&parent)
+    .with_context(|| {
+      format!(
+        "Failed to create staging directory in: {}",
+        parent.display()
+      )
+    })
+}
+
+/// Swaps the directory `staged` in for `live`.  The previous copy is kept next to `staged` until
+/// the swap has gone through, if it fails `live` is left as it was.
+pub fn replace_dir(staged: &Path, live: &Path) -> Result<()> {
+  let previous = staged.with_extension("previous");
+  let has_previous = live.exists();
+  if has_previous {
+    std::fs::rename(live, &previous)
+      .with_context(|| format!("Failed to move aside: {}", live.display()))?;
+  } else if let Some(parent) = live.parent() {
+    create_dir(parent)?;
+  }
+
+  if let Err(err) = std::fs::rename(staged, live) {
+    if has_previous && let Err(restore_err) = std::fs::rename(&previous, live) {
+      tracing::error!("Unable to restore {}: {restore_err}", live.display());
+    }
+    return Err(err).with_context(|| format!("Failed to replace directory: {}", live.display()));
+  }
+  if has_previous && let Err(err) = delete_dir(&previous) {
+    tracing::warn!(
+      "Unable to delete the previous copy of {}: {err:#}",
+      live.display()
+    );
+  }
+  Ok(())
+}
+
+/// Hex encoded SHA-256 digest of a file's contents
+pub fn sha256_file(path: impl AsRef<Path>) -> Result<String> {
+  let path = path.as_ref();
+  let mut file =
+    File::open(path).with_context(|| format!("Unable to open {} to hash", path.display()))?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher)
+    .with_context(|| format!("Unable to hash {}", path.display()))?;
+  Ok(hasher.finalize().to_vec().to_hex())
+}
+
 // TODO: come back to these last two functions later
 pub fn get_image_file_type(hex: &str) -> &str {
   if hex.starts_with("ffd8ffe0") {
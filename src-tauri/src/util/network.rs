@@ -1,33 +1,251 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use tokio;
 use tokio::io::AsyncWriteExt;
 
-pub async fn download_file(url: &str, destination: &Path) -> Result<()> {
-  if let Some(parent) = destination.parent() {
-    tokio::fs::create_dir_all(parent).await?;
+use crate::util::cancellation::{self, OperationCancelled};
+use crate::util::progress::{ProgressReporter, ProgressStage};
+
+// Downloads are written to `<destination>.part` and only moved into place once complete.  If the
+// server supports it, what's needed to resume the download is kept next to it, so an interrupted
+// download can pick up where it left off.
+#[derive(Debug, Serialize, Deserialize)]
+struct PartialDownload {
+  url: String,
+  // Sent back as `If-Range`, so a file that changed on the server is never stitched onto the old one
+  validator: String,
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+  let mut path = path.as_os_str().to_owned();
+  path.push(suffix);
+  PathBuf::from(path)
+}
+
+fn header_str(response: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<&str> {
+  response.headers().get(name)?.to_str().ok()
+}
+
+// Only strong validators can be used for `If-Range`
+fn resume_validator(response: &reqwest::Response) -> Option<String> {
+  let accepts_ranges =
+    header_str(response, ACCEPT_RANGES).is_some_and(|ranges| ranges.eq_ignore_ascii_case("bytes"));
+  if !accepts_ranges {
+    return None;
   }
+  header_str(response, ETAG)
+    .filter(|etag| !etag.starts_with("W/"))
+    .or_else(|| header_str(response, LAST_MODIFIED))
+    .map(str::to_owned)
+}
 
-  let mut response = reqwest::get(url)
-    .await
-    .with_context(|| format!("Failed to download file from: {url}"))?
-    .error_for_status()
-    .with_context(|| format!("Server returned error for {url}"))?;
+// "bytes <start>-<end>/<total>"
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+  header_str(response, CONTENT_RANGE)?
+    .strip_prefix("bytes ")?
+    .split('-')
+    .next()?
+    .parse()
+    .ok()
+}
 
-  let mut file = tokio::fs::File::create(destination)
-    .await
-    .with_context(|| {
-      format!(
-        "Failed to create destination file {}",
-        destination.display()
+fn read_partial_download(part_path: &Path, meta_path: &Path, url: &str) -> Option<(u64, String)> {
+  let downloaded = std::fs::metadata(part_path).ok()?.len();
+  let meta: PartialDownload = serde_json::from_slice(&std::fs::read(meta_path).ok()?).ok()?;
+  (meta.url == url && downloaded > 0).then_some((downloaded, meta.validator))
+}
+
+/// Downloads files over http, relative urls are resolved against `base_url`
+#[derive(Debug, Clone)]
+pub struct Downloader {
+  client: reqwest::Client,
+  base_url: Option<reqwest::Url>,
+}
+
+impl Downloader {
+  pub fn new(client: reqwest::Client, base_url: Option<reqwest::Url>) -> Self {
+    Self { client, base_url }
+  }
+
+  fn resolve(&self, url: &str) -> Result<String> {
+    let resolved = match (&self.base_url, reqwest::Url::parse(url)) {
+      (_, Ok(url)) => url,
+      (Some(base_url), Err(_)) => base_url.join(url)?,
+      (None, Err(err)) => return Err(err).with_context(|| format!("Invalid url: {url}")),
+    };
+    Ok(resolved.to_string())
+  }
+
+  /// Fetches the SHA-256 digest published next to a release asset (`<url>.sha256`).
+  ///
+  /// `None` if the release doesn't have one, and also if it can't be fetched or doesn't look like
+  /// a digest: the asset itself has already been downloaded fine by then, an error page from a
+  /// rate limit shouldn't fail that.
+  pub async fn download_checksum(&self, url: &str) -> Option<String> {
+    let checksum_url = format!("{url}.sha256");
+    let contents = async {
+      let response = self.client.get(self.resolve(&checksum_url)?).send().await?;
+      if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+      }
+      Ok::<_, anyhow::Error>(Some(response.error_for_status()?.text().await?))
+    };
+    let contents = match contents.await {
+      Ok(contents) => contents?,
+      Err(err) => {
+        tracing::warn!("Unable to download checksum from {checksum_url}: {err:#}");
+        return None;
+      }
+    };
+
+    // Usually in the `sha256sum` format of "<digest>  <file name>"
+    let digest = contents
+      .split_whitespace()
+      .next()
+      .filter(|digest| digest.len() == 64 && digest.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    if digest.is_none() {
+      tracing::warn!("{checksum_url} doesn't hold a SHA-256 digest, ignoring it");
+    }
+    digest.map(str::to_owned)
+  }
+
+  pub async fn download_file(
+    &self,
+    url: &str,
+    destination: &Path,
+    operation_id: Option<&str>,
+  ) -> Result<()> {
+    let url = &self.resolve(url)?;
+    if let Some(parent) = destination.parent() {
+      tokio::fs::create_dir_all(parent).await?;
+    }
+    let part_path = with_suffix(destination, ".part");
+    let meta_path = with_suffix(destination, ".part.json");
+    let client = &self.client;
+
+    let partial = read_partial_download(&part_path, &meta_path, url);
+    let mut request = client.get(url);
+    if let Some((downloaded, validator)) = &partial {
+      request = request
+        .header(RANGE, format!("bytes={downloaded}-"))
+        .header(IF_RANGE, validator);
+    }
+    let mut response = request
+      .send()
+      .await
+      .with_context(|| format!("Failed to download file from: {url}"))?;
+
+    let resume_from = partial.and_then(|(downloaded, _)| {
+      (response.status() == StatusCode::PARTIAL_CONTENT
+        && content_range_start(&response) == Some(downloaded))
+      .then_some(downloaded)
+    });
+    // The server couldn't give us the rest of the file, start over
+    if resume_from.is_none()
+      && matches!(
+        response.status(),
+        StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE
       )
-    })?;
+    {
+      tracing::warn!("Unable to resume download of {url}, downloading it again");
+      response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download file from: {url}"))?;
+    }
+    let mut response = response
+      .error_for_status()
+      .with_context(|| format!("Server returned error for {url}"))?;
+
+    let mut file = match resume_from {
+      Some(downloaded) => {
+        tracing::info!("Resuming download of {url} from byte {downloaded}");
+        tokio::fs::OpenOptions::new()
+          .append(true)
+          .open(&part_path)
+          .await
+          .with_context(|| format!("Failed to open partial download {}", part_path.display()))?
+      }
+      None => {
+        match resume_validator(&response) {
+          Some(validator) => {
+            let meta = PartialDownload {
+              url: url.to_owned(),
+              validator,
+            };
+            tokio::fs::write(&meta_path, serde_json::to_vec(&meta)?).await?;
+          }
+          None => {
+            let _ = tokio::fs::remove_file(&meta_path).await;
+          }
+        }
+        tokio::fs::File::create(&part_path)
+          .await
+          .with_context(|| format!("Failed to create destination file {}", part_path.display()))?
+      }
+    };
 
-  while let Some(chunk) = response.chunk().await? {
-    file.write_all(&chunk).await?;
+    let resumed_bytes = resume_from.unwrap_or_default();
+    let total_bytes = response
+      .content_length()
+      .map(|remaining| resumed_bytes + remaining);
+    let mut progress = ProgressReporter::new(operation_id, ProgressStage::Download, total_bytes)
+      .resume_from(resumed_bytes);
+    let cancel_token = operation_id.and_then(cancellation::token);
+    loop {
+      let chunk = tokio::select! {
+        chunk = response.chunk() => chunk?,
+        _ = cancellation::cancelled(cancel_token.as_ref()) => {
+          // A cancelled download is not meant to be resumed
+          drop(file);
+          let _ = tokio::fs::remove_file(&part_path).await;
+          let _ = tokio::fs::remove_file(&meta_path).await;
+          return Err(OperationCancelled(operation_id.unwrap_or_default().to_owned()).into());
+        }
+      };
+      let Some(chunk) = chunk else {
+        break;
+      };
+      file.write_all(&chunk).await?;
+      progress.advance(chunk.len() as u64);
+    }
+    file.flush().await?;
+    progress.finish();
+    drop(file);
+
+    tokio::fs::rename(&part_path, destination)
+      .await
+      .with_context(|| {
+        format!(
+          "Failed to move finished download to {}",
+          destination.display()
+        )
+      })?;
+    let _ = tokio::fs::remove_file(&meta_path).await;
+    Ok(())
   }
+}
+
+pub async fn download_file(
+  url: &str,
+  destination: &Path,
+  operation_id: Option<&str>,
+) -> Result<()> {
+  Downloader::new(reqwest::Client::new(), None)
+    .download_file(url, destination, operation_id)
+    .await
+}
 
-  Ok(())
+/// Fetches the SHA-256 digest published next to a release asset (`<url>.sha256`), `None` if
+/// there isn't a usable one
+pub async fn download_checksum(url: &str) -> Option<String> {
+  Downloader::new(reqwest::Client::new(), None)
+    .download_checksum(url)
+    .await
 }
 
 pub async fn download_json(url: &str) -> Result<String> {
@@ -42,3 +260,257 @@ pub async fn download_json(url: &str) -> Result<String> {
     .await
     .with_context(|| format!("Failed to read response body from {url}"))
 }
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use std::sync::{Arc, Mutex};
+  use tokio::io::AsyncReadExt;
+  use tokio::net::TcpListener;
+
+  use super::*;
+
+  type Headers = HashMap<String, String>;
+
+  struct Reply {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+    // Hang up halfway through the body, like a dropped connection would
+    truncated: bool,
+  }
+
+  impl Reply {
+    fn new(status: u16, body: &[u8]) -> Self {
+      Self {
+        status,
+        headers: Vec::new(),
+        body: body.to_vec(),
+        truncated: false,
+      }
+    }
+
+    fn header(mut self, name: &'static str, value: &str) -> Self {
+      self.headers.push((name, value.to_owned()));
+      self
+    }
+
+    fn truncated(mut self) -> Self {
+      self.truncated = true;
+      self
+    }
+  }
+
+  // Just enough of an http server to answer one request per connection, `reply` is called with the
+  // index and headers (lowercased) of every request
+  async fn serve(
+    reply: impl Fn(usize, &Headers) -> Reply + Send + Sync + 'static,
+  ) -> (Downloader, Arc<Mutex<Vec<Headers>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = reqwest::Url::parse(&format!("http://{}", listener.local_addr().unwrap()));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    tokio::spawn(async move {
+      loop {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+          let read = stream.read(&mut buf).await.unwrap();
+          if read == 0 {
+            break;
+          }
+          request.extend_from_slice(&buf[..read]);
+        }
+        let headers: Headers = String::from_utf8_lossy(&request)
+          .lines()
+          .skip(1)
+          .filter_map(|line| line.split_once(':'))
+          .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_owned()))
+          .collect();
+        let index = {
+          let mut seen = seen.lock().unwrap();
+          seen.push(headers.clone());
+          seen.len() - 1
+        };
+
+        let reply = reply(index, &headers);
+        let mut response = format!(
+          "HTTP/1.1 {} Stand-in\r\nContent-Length: {}\r\nConnection: close\r\n",
+          reply.status,
+          reply.body.len()
+        );
+        for (name, value) in &reply.headers {
+          response.push_str(&format!("{name}: {value}\r\n"));
+        }
+        response.push_str("\r\n");
+        let body = match reply.truncated {
+          true => &reply.body[..reply.body.len() / 2],
+          false => &reply.body[..],
+        };
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.write_all(body).await;
+        let _ = stream.shutdown().await;
+      }
+    });
+    let downloader = Downloader::new(reqwest::Client::new(), Some(base_url.unwrap()));
+    (downloader, requests)
+  }
+
+  fn contents() -> Vec<u8> {
+    (0..1000u32).map(|i| (i % 251) as u8).collect()
+  }
+
+  fn write_partial_download(destination: &Path, url: &str, contents: &[u8], validator: &str) {
+    std::fs::write(with_suffix(destination, ".part"), contents).unwrap();
+    let meta = PartialDownload {
+      url: url.to_owned(),
+      validator: validator.to_owned(),
+    };
+    std::fs::write(
+      with_suffix(destination, ".part.json"),
+      serde_json::to_vec(&meta).unwrap(),
+    )
+    .unwrap();
+  }
+
+  #[tokio::test]
+  async fn resumes_from_partial_download() {
+    let (downloader, requests) = serve(|index, headers| {
+      let contents = contents();
+      match headers.get("range") {
+        Some(range) if headers.get("if-range").map(String::as_str) == Some("\"v1\"") => {
+          let start: usize = range["bytes=".len()..range.len() - 1].parse().unwrap();
+          Reply::new(206, &contents[start..])
+            .header("Content-Range", &format!("bytes {start}-999/1000"))
+        }
+        _ => {
+          let reply = Reply::new(200, &contents)
+            .header("Accept-Ranges", "bytes")
+            .header("ETag", "\"v1\"");
+          if index == 0 { reply.truncated() } else { reply }
+        }
+      }
+    })
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let destination = dir.path().join("release.tar.gz");
+
+    assert!(
+      downloader
+        .download_file("/release.tar.gz", &destination, None)
+        .await
+        .is_err()
+    );
+    assert!(with_suffix(&destination, ".part.json").exists());
+
+    downloader
+      .download_file("/release.tar.gz", &destination, None)
+      .await
+      .unwrap();
+    assert_eq!(std::fs::read(&destination).unwrap(), contents());
+    assert!(!with_suffix(&destination, ".part").exists());
+    assert!(!with_suffix(&destination, ".part.json").exists());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].contains_key("range"));
+  }
+
+  #[tokio::test]
+  async fn restarts_when_the_file_changed_on_the_server() {
+    // A real server ignores the range if `If-Range` doesn't match
+    let (downloader, requests) = serve(|_, _| {
+      Reply::new(200, &contents())
+        .header("Accept-Ranges", "bytes")
+        .header("ETag", "\"v2\"")
+    })
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let destination = dir.path().join("release.tar.gz");
+    let url = downloader.resolve("/release.tar.gz").unwrap();
+    write_partial_download(&destination, &url, &[0xff; 300], "\"v1\"");
+
+    downloader
+      .download_file("/release.tar.gz", &destination, None)
+      .await
+      .unwrap();
+    assert_eq!(std::fs::read(&destination).unwrap(), contents());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0]["range"], "bytes=300-");
+    assert_eq!(requests[0]["if-range"], "\"v1\"");
+  }
+
+  #[tokio::test]
+  async fn restarts_when_the_range_is_not_satisfiable() {
+    let (downloader, requests) = serve(|_, headers| {
+      if headers.contains_key("range") {
+        Reply::new(416, &[]).header("Content-Range", "bytes */1000")
+      } else {
+        Reply::new(200, &contents())
+      }
+    })
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let destination = dir.path().join("release.tar.gz");
+    let url = downloader.resolve("/release.tar.gz").unwrap();
+    write_partial_download(&destination, &url, &[0xff; 1200], "\"v1\"");
+
+    downloader
+      .download_file("/release.tar.gz", &destination, None)
+      .await
+      .unwrap();
+    assert_eq!(std::fs::read(&destination).unwrap(), contents());
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(!requests[1].contains_key("range"));
+  }
+
+  #[tokio::test]
+  async fn does_not_resume_without_accept_ranges() {
+    let (downloader, requests) = serve(|index, _| {
+      let reply = Reply::new(200, &contents()).header("ETag", "\"v1\"");
+      if index == 0 { reply.truncated() } else { reply }
+    })
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let destination = dir.path().join("release.tar.gz");
+
+    assert!(
+      downloader
+        .download_file("/release.tar.gz", &destination, None)
+        .await
+        .is_err()
+    );
+    assert!(!with_suffix(&destination, ".part.json").exists());
+
+    downloader
+      .download_file("/release.tar.gz", &destination, None)
+      .await
+      .unwrap();
+    assert_eq!(std::fs::read(&destination).unwrap(), contents());
+    assert!(!requests.lock().unwrap()[1].contains_key("range"));
+  }
+
+  #[tokio::test]
+  async fn only_uses_checksums_that_are_digests() {
+    let digest = "ab".repeat(32);
+    let body = format!("{digest}  release.tar.gz\n");
+    let (downloader, _) = serve(move |_, _| Reply::new(200, body.as_bytes())).await;
+    assert_eq!(
+      downloader.download_checksum("/release.tar.gz").await,
+      Some(digest)
+    );
+
+    let (downloader, _) =
+      serve(|_, _| Reply::new(200, b"<html><body>Rate limited</body></html>")).await;
+    assert_eq!(downloader.download_checksum("/release.tar.gz").await, None);
+  }
+
+  #[tokio::test]
+  async fn skips_checksums_that_cant_be_fetched() {
+    for status in [403, 404, 429, 503] {
+      let (downloader, _) = serve(move |_, _| Reply::new(status, b"Forbidden")).await;
+      assert_eq!(downloader.download_checksum("/release.tar.gz").await, None);
+    }
+  }
+}
@@ -6,6 +6,7 @@ use tokio::{
   sync::mpsc,
 };
 
+use super::cancellation::{self, OperationCancelled};
 use super::file::create_dir;
 use tauri::{Emitter, Manager};
 
@@ -34,11 +35,16 @@ struct LogPayload {
   logs: String,
 }
 
+/// Streams the process's output into the log file and the frontend until it exits.
+///
+/// If `operation_id` is cancelled in the meantime, the process is killed.
 pub async fn watch_process(
   log_file: &mut tokio::fs::File,
   child: &mut tokio::process::Child,
   app_handle: &tauri::AppHandle,
+  operation_id: Option<&str>,
 ) -> Result<ExitStatus> {
+  let cancel_token = operation_id.and_then(cancellation::token);
   let stdout = child.stdout.take().context("Child stdout was not piped")?;
   let stderr = child.stderr.take().context("Child stderr was not piped")?;
   let mut stdout_lines = BufReader::new(stdout).lines();
@@ -70,6 +76,12 @@ pub async fn watch_process(
         log_file.flush().await.context("Failed flushing log file")?;
         return status.context("Failed waiting for child process");
       }
+      _ = cancellation::cancelled(cancel_token.as_ref()) => {
+        child.kill().await.context("Failed to kill cancelled process")?;
+        drop(tx);
+        log_file.flush().await.context("Failed flushing log file")?;
+        return Err(OperationCancelled(operation_id.unwrap_or_default().to_owned()).into());
+      }
     }
   }
 }
@@ -0,0 +1,164 @@
+// Progress reporting for long running operations (downloads, extraction)
+//
+// Events are emitted on `operation:progress`, tagged with the id of the operation they belong to
+// so the frontend can tell concurrent operations apart.  Reporters without an id stay silent.
+
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+use ts_rs::TS;
+
+use crate::TAURI_APP;
+use crate::util::cancellation::{self, OperationCancelled};
+
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum ProgressStage {
+  Download,
+  Extract,
+  // Moving the installation directory to another drive
+  Relocate,
+}
+
+#[derive(Debug, Serialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ProgressEvent {
+  pub operation_id: String,
+  pub stage: ProgressStage,
+  pub processed_bytes: u64,
+  pub total_bytes: Option<u64>,
+  pub bytes_per_second: u64,
+  pub eta_seconds: Option<u64>,
+}
+
+pub struct ProgressReporter {
+  operation_id: Option<String>,
+  stage: ProgressStage,
+  total_bytes: Option<u64>,
+  processed_bytes: u64,
+  // Bytes that were already done before this session (ie. a resumed download), they don't count
+  // towards the throughput
+  initial_bytes: u64,
+  started: Instant,
+  last_emit: Option<Instant>,
+  cancel_token: Option<CancellationToken>,
+}
+
+impl ProgressReporter {
+  pub fn new(operation_id: Option<&str>, stage: ProgressStage, total_bytes: Option<u64>) -> Self {
+    Self {
+      operation_id: operation_id.map(str::to_owned),
+      stage,
+      total_bytes,
+      processed_bytes: 0,
+      initial_bytes: 0,
+      started: Instant::now(),
+      last_emit: None,
+      cancel_token: operation_id.and_then(cancellation::token),
+    }
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self
+      .cancel_token
+      .as_ref()
+      .is_some_and(CancellationToken::is_cancelled)
+  }
+
+  pub fn resume_from(mut self, processed_bytes: u64) -> Self {
+    self.processed_bytes = processed_bytes;
+    self.initial_bytes = processed_bytes;
+    self
+  }
+
+  pub fn advance(&mut self, bytes: u64) {
+    self.processed_bytes += bytes;
+    if self
+      .last_emit
+      .is_none_or(|last_emit| last_emit.elapsed() >= EMIT_INTERVAL)
+    {
+      self.emit();
+    }
+  }
+
+  pub fn finish(&mut self) {
+    if let Some(total_bytes) = self.total_bytes {
+      self.processed_bytes = self.processed_bytes.max(total_bytes);
+    }
+    self.emit();
+  }
+
+  fn emit(&mut self) {
+    let Some(operation_id) = &self.operation_id else {
+      return;
+    };
+    self.last_emit = Some(Instant::now());
+
+    let elapsed = self.started.elapsed().as_secs_f64();
+    let bytes_per_second = if elapsed > 0.0 {
+      ((self.processed_bytes - self.initial_bytes) as f64 / elapsed) as u64
+    } else {
+      0
+    };
+    let eta_seconds = self.total_bytes.and_then(|total_bytes| {
+      let remaining = total_bytes.saturating_sub(self.processed_bytes);
+      (bytes_per_second > 0).then(|| remaining / bytes_per_second)
+    });
+
+    let event = ProgressEvent {
+      operation_id: operation_id.clone(),
+      stage: self.stage,
+      processed_bytes: self.total_bytes.map_or(self.processed_bytes, |total| {
+        self.processed_bytes.min(total)
+      }),
+      total_bytes: self.total_bytes,
+      bytes_per_second,
+      eta_seconds,
+    };
+    if let Some(app_handle) = TAURI_APP.get() {
+      let _ = app_handle.emit("operation:progress", event);
+    }
+  }
+}
+
+/// Reports progress as the underlying reader is consumed
+pub struct ProgressReader<R> {
+  inner: R,
+  reporter: ProgressReporter,
+}
+
+impl<R> ProgressReader<R> {
+  pub fn new(inner: R, reporter: ProgressReporter) -> Self {
+    Self { inner, reporter }
+  }
+
+  pub fn finish(&mut self) {
+    self.reporter.finish();
+  }
+}
+
+// Cancelling the operation fails the next read, aborting whatever is consuming the reader
+impl<R: Read> Read for ProgressReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    if self.reporter.is_cancelled() {
+      let operation_id = self.reporter.operation_id.clone().unwrap_or_default();
+      return Err(std::io::Error::other(OperationCancelled(operation_id)));
+    }
+    let read = self.inner.read(buf)?;
+    self.reporter.advance(read as u64);
+    Ok(read)
+  }
+}
+
+// Archive formats like zip jump around, so this only approximates how far along things are
+impl<R: Seek> Seek for ProgressReader<R> {
+  fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    self.inner.seek(pos)
+  }
+}
@@ -1,51 +1,201 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tar::EntryType;
 
-use crate::util::zip::{extract_and_delete_zip_file, extract_zip_file};
+use crate::util::archive::{
+  ArchiveEntry, ArchiveFormat, ExtractionError, ExtractionLimits, SafeExtractor, common_top_dir,
+  sanitize_entry_path,
+};
+use crate::util::progress::{ProgressReader, ProgressReporter, ProgressStage};
+use crate::util::zip::{extract_and_delete_zip_file, extract_zip_file, visit_zip_entries};
 
-pub fn extract_tar_ball(tar_path: impl AsRef<Path>, extract_dir: impl AsRef<Path>) -> Result<()> {
+fn decompress<'a>(reader: impl Read + 'a, format: ArchiveFormat) -> Result<Box<dyn Read + 'a>> {
+  let reader = BufReader::new(reader);
+  Ok(match format {
+    ArchiveFormat::Tar => Box::new(reader),
+    ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(reader)),
+    ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(reader)),
+    ArchiveFormat::TarZst => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+    ArchiveFormat::Zip => anyhow::bail!("Not a tar archive"),
+  })
+}
+
+fn entry_name(entry: &tar::Entry<impl Read>) -> String {
+  String::from_utf8_lossy(&entry.path_bytes()).into_owned()
+}
+
+/// Calls `visit` with every entry of the tarball and a reader for its contents
+pub fn visit_tar_entries(
+  tar_path: &Path,
+  format: ArchiveFormat,
+  mut visit: impl FnMut(&ArchiveEntry, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+  let file = std::fs::File::open(tar_path)
+    .with_context(|| format!("failed to open: {}", tar_path.display()))?;
+  let mut archive = tar::Archive::new(decompress(file, format)?);
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_pax_global_extensions() {
+      continue;
+    }
+    if let Some(archive_entry) =
+      ArchiveEntry::new(&entry_name(&entry), entry.size(), entry_type.is_dir())?
+    {
+      visit(&archive_entry, &mut entry)?;
+    }
+  }
+  Ok(())
+}
+
+// Unlike a zip, a tarball has no index, so finding out whether everything is in one folder takes
+// a pass over the whole archive
+fn find_top_dir(tar_path: &Path, format: ArchiveFormat) -> Result<Option<PathBuf>> {
+  let mut paths = Vec::new();
+  visit_tar_entries(tar_path, format, |entry, _| {
+    paths.push(PathBuf::from(&entry.path));
+    Ok(())
+  })?;
+  Ok(common_top_dir(paths.iter().map(|path| path.as_path())))
+}
+
+pub(crate) fn unpack(
+  archive: &mut tar::Archive<impl Read>,
+  extract_dir: &Path,
+  strip_dir: Option<&Path>,
+  limits: ExtractionLimits,
+) -> Result<()> {
+  let mut extractor = SafeExtractor::new(extract_dir, limits);
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_pax_global_extensions() {
+      continue;
+    }
+    let name = entry_name(&entry);
+    let Some(relative_path) = extractor.entry_path(&name, strip_dir)? else {
+      continue;
+    };
+    let link_name = entry
+      .link_name_bytes()
+      .map(|link_name| String::from_utf8_lossy(&link_name).into_owned());
+    match (entry_type, link_name) {
+      (EntryType::Directory, _) => extractor.create_dir(&relative_path)?,
+      (EntryType::Regular | EntryType::Continuous, _) => {
+        let mode = entry.header().mode().ok();
+        extractor.write_file(&relative_path, &mut entry, mode)?;
+      }
+      (EntryType::Symlink, Some(link_name)) => {
+        extractor.create_symlink(&relative_path, &link_name)?
+      }
+      (EntryType::Link, Some(link_name)) => {
+        // Hard links name the entry they link to, which has been moved just the same
+        let link_name = match strip_dir {
+          Some(strip_dir) => sanitize_entry_path(&link_name)?
+            .strip_prefix(strip_dir)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or(link_name),
+          None => link_name,
+        };
+        extractor.create_hard_link(&relative_path, &link_name)?
+      }
+      _ => Err(ExtractionError::SpecialFile(name))?,
+    }
+  }
+  Ok(())
+}
+
+pub fn extract_tar_ball(
+  tar_path: impl AsRef<Path>,
+  extract_dir: impl AsRef<Path>,
+  format: ArchiveFormat,
+  strip_top_dir: bool,
+  operation_id: Option<&str>,
+) -> Result<()> {
   let tar_path = tar_path.as_ref();
   let extract_dir = extract_dir.as_ref();
-  let tar_gz = std::fs::File::open(tar_path)
+  let strip_dir = if strip_top_dir {
+    find_top_dir(tar_path, format)
+      .with_context(|| format!("failed to read: {}", tar_path.display()))?
+  } else {
+    None
+  };
+
+  let tar_file = std::fs::File::open(tar_path)
     .with_context(|| format!("failed to open: {}", tar_path.display()))?;
-  let tar = flate2::read::GzDecoder::new(tar_gz);
-  let mut archive = tar::Archive::new(tar);
-  archive.unpack(extract_dir).with_context(|| {
+  let total_bytes = tar_file.metadata().map(|metadata| metadata.len()).ok();
+  let mut reader = ProgressReader::new(
+    tar_file,
+    ProgressReporter::new(operation_id, ProgressStage::Extract, total_bytes),
+  );
+  let mut archive = tar::Archive::new(decompress(&mut reader, format)?);
+  unpack(
+    &mut archive,
+    extract_dir,
+    strip_dir.as_deref(),
+    ExtractionLimits::default(),
+  )
+  .with_context(|| {
     format!(
       "failed to unpack: {} into {}",
       tar_path.display(),
       extract_dir.display()
     )
   })?;
+  drop(archive);
+  reader.finish();
   Ok(())
 }
 
 pub fn extract_and_delete_tar_ball(
   tar_path: impl AsRef<Path>,
   extract_dir: impl AsRef<Path>,
+  format: ArchiveFormat,
+  strip_top_dir: bool,
+  operation_id: Option<&str>,
 ) -> Result<()> {
   let tar_path = tar_path.as_ref();
   let extract_dir = extract_dir.as_ref();
-  extract_tar_ball(tar_path, extract_dir)?;
+  extract_tar_ball(tar_path, extract_dir, format, strip_top_dir, operation_id)?;
   std::fs::remove_file(tar_path)
     .with_context(|| format!("failed to delete: {}", tar_path.display()))?;
   Ok(())
 }
 
-pub fn extract_archive(archive: &Path, dest: &Path) -> Result<()> {
+pub fn extract_archive(
+  archive: &Path,
+  dest: &Path,
+  strip_top_dir: bool,
+  operation_id: Option<&str>,
+) -> Result<()> {
   std::fs::create_dir_all(dest)?;
-  match archive.extension().and_then(|e| e.to_str()) {
-    Some("zip") => extract_zip_file(archive, dest, false),
-    Some("gz") => extract_tar_ball(archive, dest),
-    _ => anyhow::bail!("Unsupported archive format (expected .zip or .tar.gz)"),
+  match ArchiveFormat::detect(archive)? {
+    ArchiveFormat::Zip => extract_zip_file(archive, dest, strip_top_dir, operation_id),
+    format => extract_tar_ball(archive, dest, format, strip_top_dir, operation_id),
   }
 }
 
-pub fn extract_and_delete_archive(archive: &Path, dest: &Path, strip_top_dir: bool) -> Result<()> {
+pub fn extract_and_delete_archive(
+  archive: &Path,
+  dest: &Path,
+  strip_top_dir: bool,
+  operation_id: Option<&str>,
+) -> Result<()> {
   std::fs::create_dir_all(dest)?;
-  match archive.extension().and_then(|e| e.to_str()) {
-    Some("zip") => extract_and_delete_zip_file(archive, dest, strip_top_dir),
-    Some("gz") => extract_and_delete_tar_ball(archive, dest),
-    _ => anyhow::bail!("Unsupported archive format (expected .zip or .tar.gz)"),
+  match ArchiveFormat::detect(archive)? {
+    ArchiveFormat::Zip => extract_and_delete_zip_file(archive, dest, strip_top_dir, operation_id),
+    format => extract_and_delete_tar_ball(archive, dest, format, strip_top_dir, operation_id),
+  }
+}
+
+pub fn visit_archive_entries(
+  archive: &Path,
+  format: ArchiveFormat,
+  visit: impl FnMut(&ArchiveEntry, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+  match format {
+    ArchiveFormat::Zip => visit_zip_entries(archive, visit),
+    format => visit_tar_entries(archive, format, visit),
   }
 }
@@ -0,0 +1,205 @@
+// Helpers for the tooling binaries that make up a version (`gk`, `extractor` and `goalc`)
+//
+// Before a version is put to use its binaries are probed: `extractor` and `goalc` are run with
+// `--version` (falling back to `--help`) to make sure they actually start on this machine and to
+// learn which version they were built as.  `gk` is only checked to be there and executable, older
+// builds don't know `--version` and boot the game (opening its window) instead.  The result is
+// recorded in the version's folder, so capability checks can go by what the binaries report
+// rather than whatever the folder happens to be called.
+
+use anyhow::{Context, Result, bail};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use ts_rs::TS;
+
+pub const BINARIES: [&str; 3] = ["gk", "extractor", "goalc"];
+// Binaries that are never run while probing
+const NOT_RUN_BINARIES: [&str; 1] = ["gk"];
+
+// How long a probed binary gets to answer each flag, builds that don't know the flag might carry
+// on as if they were started normally
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_FILE_NAME: &str = ".probe.json";
+// The probed binaries get a clean environment, except for what they need to start at all
+const PROBE_ENV: [&str; 6] = ["PATH", "SystemRoot", "windir", "TEMP", "TMP", "HOME"];
+
+pub fn binary_file_name(binary: &str) -> String {
+  if cfg!(windows) {
+    format!("{binary}.exe")
+  } else {
+    binary.to_string()
+  }
+}
+
+/// The binaries (file names) `dir` is lacking
+pub fn missing_binaries(dir: &Path) -> Vec<String> {
+  BINARIES
+    .into_iter()
+    .map(binary_file_name)
+    .filter(|binary| !dir.join(binary).is_file())
+    .collect()
+}
+
+// The first thing that looks like a version, ie. "OpenGOAL Compiler v0.2.14 (abcdef)"
+fn find_version(output: &str) -> Option<Version> {
+  output
+    .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '[' | ']'))
+    .find_map(|token| Version::parse(token.trim_start_matches('v')).ok())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BinaryProbe {
+  pub binary: String,
+  pub version: Option<String>,
+  // The first line of output, usually the name and the build's commit
+  pub build_info: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ToolingProbe {
+  /// The version reported by the binaries, if any of them did
+  pub version: Option<String>,
+  pub binaries: Vec<BinaryProbe>,
+}
+
+impl ToolingProbe {
+  pub fn version(&self) -> Option<Version> {
+    Version::parse(self.version.as_deref()?).ok()
+  }
+
+  /// The probe recorded for the version in `dir`, if it has been probed
+  pub fn load(dir: &Path) -> Option<Self> {
+    let contents = std::fs::read(dir.join(PROBE_FILE_NAME)).ok()?;
+    serde_json::from_slice(&contents)
+      .inspect_err(|err| tracing::warn!("Ignoring unreadable probe in {}: {err}", dir.display()))
+      .ok()
+  }
+
+  pub fn save(&self, dir: &Path) -> Result<()> {
+    let path = dir.join(PROBE_FILE_NAME);
+    std::fs::write(&path, serde_json::to_vec_pretty(self)?)
+      .with_context(|| format!("Unable to record probe in {}", path.display()))
+  }
+}
+
+#[cfg(unix)]
+fn ensure_executable(path: &Path) -> Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  let mode = std::fs::metadata(path)?.permissions().mode();
+  if mode & 0o111 == 0 {
+    bail!("{} is not executable", path.display());
+  }
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn ensure_executable(_path: &Path) -> Result<()> {
+  Ok(())
+}
+
+// What the binary printed when run with `flag`, `None` if it didn't exit within `PROBE_TIMEOUT`
+async fn run_probe(path: &Path, flag: &str, sandbox_dir: &Path) -> Result<Option<String>> {
+  let mut command = tokio::process::Command::new(path);
+  command
+    .arg(flag)
+    .env_clear()
+    .envs(
+      PROBE_ENV
+        .into_iter()
+        .filter_map(|name| Some((name, std::env::var_os(name)?))),
+    )
+    .current_dir(sandbox_dir)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  #[cfg(windows)]
+  {
+    command.creation_flags(0x08000000);
+  }
+
+  let Ok(output) = tokio::time::timeout(PROBE_TIMEOUT, command.output()).await else {
+    tracing::warn!("{} did not respond to {flag} in time", path.display());
+    return Ok(None);
+  };
+  let output = output.with_context(|| format!("Unable to run {}", path.display()))?;
+  Ok(Some(format!(
+    "{}\n{}",
+    String::from_utf8_lossy(&output.stdout),
+    String::from_utf8_lossy(&output.stderr)
+  )))
+}
+
+async fn probe_binary(dir: &Path, binary: &str, sandbox_dir: &Path) -> Result<BinaryProbe> {
+  let path = dir.join(binary_file_name(binary));
+  if !path.is_file() {
+    bail!("{} is missing", path.display());
+  }
+  ensure_executable(&path)?;
+  if NOT_RUN_BINARIES.contains(&binary) {
+    return Ok(BinaryProbe {
+      binary: binary.to_owned(),
+      version: None,
+      build_info: None,
+    });
+  }
+
+  // Not knowing `--version` and hanging on it are treated alike, `--help` gets a go either way
+  let output = match run_probe(&path, "--version", sandbox_dir).await? {
+    Some(output) if find_version(&output).is_some() => output,
+    _ => run_probe(&path, "--help", sandbox_dir)
+      .await?
+      .with_context(|| {
+        format!(
+          "Probe timed out, {} did not respond to --version or --help within {}s",
+          path.display(),
+          PROBE_TIMEOUT.as_secs()
+        )
+      })?,
+  };
+  Ok(BinaryProbe {
+    binary: binary.to_owned(),
+    version: find_version(&output).map(|version| version.to_string()),
+    build_info: output
+      .lines()
+      .map(str::trim)
+      .find(|line| !line.is_empty())
+      .map(str::to_owned),
+  })
+}
+
+/// Runs the binaries in `dir`, failing if any of them is missing or can't be run
+pub async fn probe(dir: &Path) -> Result<ToolingProbe> {
+  // Whatever the binaries decide to write ends up here rather than next to them
+  let sandbox_dir = tempfile::tempdir()?;
+  let mut binaries = Vec::new();
+  for binary in BINARIES {
+    binaries.push(probe_binary(dir, binary, sandbox_dir.path()).await?);
+  }
+
+  let version = binaries.iter().find_map(|binary| binary.version.clone());
+  match &version {
+    Some(version) => tracing::info!("Probed {}, reports version {version}", dir.display()),
+    None => tracing::warn!(
+      "Probed {}, but none of the binaries report a version",
+      dir.display()
+    ),
+  }
+  Ok(ToolingProbe { version, binaries })
+}
+
+/// Probes `dir` and records the result alongside the binaries
+pub async fn probe_and_record(dir: &Path) -> Result<ToolingProbe> {
+  let probe = probe(dir).await?;
+  probe.save(dir)?;
+  Ok(probe)
+}
@@ -1,14 +1,26 @@
 use anyhow::{Context, Result};
-use std::io::{BufReader, Cursor};
+use std::io::BufReader;
 use std::{
   fs::File,
-  io::{Read, Write},
+  io::{Read, Seek, Write},
   path::Path,
 };
 use tracing::info;
 use walkdir::WalkDir;
 use zip::write::SimpleFileOptions;
 
+use crate::util::archive::{
+  ArchiveEntry, ExtractionError, ExtractionLimits, SafeExtractor, common_top_dir,
+  sanitize_entry_path,
+};
+use crate::util::progress::{ProgressReader, ProgressReporter, ProgressStage};
+
+// File type bits of an entry's unix mode
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+
 pub fn append_dir_contents_to_zip(
   zip_file: &mut zip::ZipWriter<&File>,
   dir: &Path,
@@ -97,9 +109,85 @@ pub fn extract_zip_file(
   zip_path: impl AsRef<Path>,
   extract_dir: &Path,
   strip_top_dir: bool,
+  operation_id: Option<&str>,
+) -> Result<()> {
+  let zip_path = zip_path.as_ref();
+  // Read straight from disk, these can be hundreds of megabytes
+  let file = File::open(zip_path)
+    .with_context(|| format!("Unable to open zip file {}", zip_path.display()))?;
+  let total_bytes = file.metadata().map(|metadata| metadata.len()).ok();
+  let reader = ProgressReader::new(
+    BufReader::new(file),
+    ProgressReporter::new(operation_id, ProgressStage::Extract, total_bytes),
+  );
+  let mut zip = zip::ZipArchive::new(reader).context("Failed to read zip archive")?;
+
+  let strip_dir = if strip_top_dir {
+    let paths = zip
+      .file_names()
+      .map(sanitize_entry_path)
+      .collect::<Result<Vec<_>, _>>()?;
+    common_top_dir(paths.iter().map(|path| path.as_path()))
+  } else {
+    None
+  };
+
+  unpack(
+    &mut zip,
+    extract_dir,
+    strip_dir.as_deref(),
+    ExtractionLimits::default(),
+  )?;
+  zip.into_inner().finish();
+  Ok(())
+}
+
+pub(crate) fn unpack(
+  zip: &mut zip::ZipArchive<impl Read + Seek>,
+  extract_dir: &Path,
+  strip_dir: Option<&Path>,
+  limits: ExtractionLimits,
+) -> Result<()> {
+  let mut extractor = SafeExtractor::new(extract_dir, limits);
+  for i in 0..zip.len() {
+    let mut file = zip
+      .by_index(i)
+      .context("Failed reading entry from zip archive")?;
+    let Some(relative_path) = extractor.entry_path(file.name(), strip_dir)? else {
+      continue;
+    };
+    let mode = file.unix_mode();
+    match mode.map(|mode| mode & S_IFMT) {
+      _ if file.is_dir() => extractor.create_dir(&relative_path)?,
+      Some(S_IFDIR) => extractor.create_dir(&relative_path)?,
+      Some(S_IFLNK) => {
+        let mut link_target = String::new();
+        file.read_to_string(&mut link_target)?;
+        extractor.create_symlink(&relative_path, &link_target)?;
+      }
+      None | Some(0) | Some(S_IFREG) => extractor.write_file(&relative_path, &mut file, mode)?,
+      Some(_) => Err(ExtractionError::SpecialFile(file.name().to_owned()))?,
+    }
+  }
+  Ok(())
+}
+
+/// Calls `visit` with every entry of the zip and a reader for its contents
+pub fn visit_zip_entries(
+  zip_path: &Path,
+  mut visit: impl FnMut(&ArchiveEntry, &mut dyn Read) -> Result<()>,
 ) -> Result<()> {
-  let archive: Vec<u8> = std::fs::read(zip_path)?;
-  zip_extract::extract(Cursor::new(archive), extract_dir, strip_top_dir)?;
+  let file = File::open(zip_path)
+    .with_context(|| format!("Unable to open zip file {}", zip_path.display()))?;
+  let mut zip = zip::ZipArchive::new(BufReader::new(file)).context("Failed to read zip archive")?;
+  for i in 0..zip.len() {
+    let mut file = zip
+      .by_index(i)
+      .context("Failed reading entry from zip archive")?;
+    if let Some(entry) = ArchiveEntry::new(file.name(), file.size(), file.is_dir())? {
+      visit(&entry, &mut file)?;
+    }
+  }
   Ok(())
 }
 
@@ -107,8 +195,9 @@ pub fn extract_and_delete_zip_file(
   zip_path: impl AsRef<Path>,
   extract_dir: &Path,
   strip_top_dir: bool,
+  operation_id: Option<&str>,
 ) -> Result<()> {
-  extract_zip_file(&zip_path, extract_dir, strip_top_dir)?;
+  extract_zip_file(&zip_path, extract_dir, strip_top_dir, operation_id)?;
   std::fs::remove_file(&zip_path)?;
   Ok(())
 }
@@ -11,9 +11,10 @@ export async function updateDataDirectory(
 export async function extractAndValidateISO(
   pathToIso: string,
   gameName: string,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("extract_and_validate_iso", {
-    args: { pathToIso, gameName },
+    args: { pathToIso, gameName, operationId },
   });
 }
 
@@ -22,6 +23,7 @@ export async function runDecompiler(
   gameName: string,
   truncateLogs: boolean = false,
   useDecompSettings: boolean = false,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("run_decompiler", {
     args: {
@@ -29,6 +31,7 @@ export async function runDecompiler(
       gameName,
       truncateLogs,
       useDecompSettings,
+      operationId,
     },
   });
 }
@@ -37,12 +40,14 @@ export async function runCompiler(
   pathToIso: string | null,
   gameName: string,
   truncateLogs: boolean = false,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("run_compiler", {
     args: {
       pathToIso,
       gameName,
       truncateLogs,
+      operationId,
     },
   });
 }
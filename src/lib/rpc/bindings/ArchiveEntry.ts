@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An entry of an archive as it would be extracted, paths are always separated by `/`
+ */
+export type ArchiveEntry = { path: string; size: bigint; isDir: boolean };
@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArchiveEntry } from "./ArchiveEntry";
+import type { ArchiveFormat } from "./ArchiveFormat";
+
+/**
+ * What an archive would install, so it can be confirmed before anything is extracted
+ */
+export type ArchiveInspection = {
+  format: ArchiveFormat;
+  entries: Array<ArchiveEntry>;
+  totalSize: bigint;
+  /**
+   * The folder everything is in, if there is only one
+   */
+  topLevelFolder: string | null;
+  hasGk: boolean;
+  hasExtractor: boolean;
+  hasGoalc: boolean;
+  metadataPath: string | null;
+  /**
+   * The raw contents of the metadata file, it isn't validated
+   */
+  metadata: string | null;
+  /**
+   * Every `texture_replacements` folder in the archive
+   */
+  textureReplacementPaths: Array<string>;
+};
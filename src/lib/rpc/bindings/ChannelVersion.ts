@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { VersionChannel } from "./VersionChannel";
+
+/**
+ * A tooling version along with the channel it was taken from
+ */
+export type ChannelVersion = { channel: VersionChannel; version: string };
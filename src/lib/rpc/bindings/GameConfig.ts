@@ -1,8 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelVersion } from "./ChannelVersion";
 
 export type GameConfig = {
   isInstalled: boolean;
-  version: string | null;
+  version: ChannelVersion | null;
+  pinnedVersion: ChannelVersion | null;
   texturePacks: Array<string>;
   secondsPlayed: bigint;
   mods: { [key in string]: { [key in string]: string } };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SupportedGame } from "./SupportedGame";
+
+export type InstallFix =
+  | { kind: "clearActiveVersion" }
+  | { kind: "restoreActiveBinaries" }
+  | { kind: "markGameUninstalled"; game: SupportedGame }
+  | { kind: "unpinGameVersion"; game: SupportedGame }
+  | { kind: "forgetMod"; game: SupportedGame; source: string; name: string }
+  | { kind: "disableTexturePack"; game: SupportedGame; name: string };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InstallFix } from "./InstallFix";
+import type { MissingContent } from "./MissingContent";
+
+export type InstallProblem = {
+  missing: MissingContent;
+  path: string;
+  suggestedFix: InstallFix;
+};
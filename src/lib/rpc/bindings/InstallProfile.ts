@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelVersion } from "./ChannelVersion";
+import type { GameConfig } from "./GameConfig";
+import type { SupportedGame } from "./SupportedGame";
+
+export type InstallProfile = {
+  installationDir: string | null;
+  activeVersion: ChannelVersion | null;
+  games: { [key in SupportedGame]?: GameConfig };
+};
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelVersion } from "./ChannelVersion";
+
+export type InstallProfileInfo = {
+  name: string;
+  installationDir: string | null;
+  activeVersion: ChannelVersion | null;
+  isActive: boolean;
+};
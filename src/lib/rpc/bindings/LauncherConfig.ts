@@ -1,16 +1,21 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelVersion } from "./ChannelVersion";
 import type { DecompilerSettings } from "./DecompilerSettings";
 import type { GameConfig } from "./GameConfig";
+import type { InstallProfile } from "./InstallProfile";
 import type { Requirements } from "./Requirements";
 import type { SupportedGame } from "./SupportedGame";
 import type { UISettings } from "./UISettings";
 
 export type LauncherConfig = {
   version: string;
+  overriddenFields?: Array<string>;
   requirements: Requirements;
   games: { [key in SupportedGame]?: GameConfig };
   installationDir: string | null;
-  activeVersion: string | null;
+  activeVersion: ChannelVersion | null;
+  activeProfile: string;
+  inactiveProfiles: { [key in string]: InstallProfile };
   locale: string;
   modSources: Array<string>;
   decompilerSettings: DecompilerSettings;
@@ -18,5 +23,7 @@ export type LauncherConfig = {
   proceedAfterSuccessfulOperation: boolean;
   autoUpdateGames: boolean;
   deletePreviousVersions: boolean;
+  versionsToKeep: number;
+  releaseIndexUrl: string | null;
   ui: UISettings;
 };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SupportedGame } from "./SupportedGame";
+
+export type MissingContent =
+  | { kind: "activeVersion"; version: string }
+  | { kind: "activeBinaries"; binaries: Array<string> }
+  | { kind: "gameData"; game: SupportedGame }
+  | { kind: "pinnedVersion"; game: SupportedGame; version: string }
+  | { kind: "mod"; game: SupportedGame; source: string; name: string }
+  | { kind: "texturePack"; game: SupportedGame; name: string };
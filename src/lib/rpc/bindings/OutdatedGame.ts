@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelVersion } from "./ChannelVersion";
+import type { SupportedGame } from "./SupportedGame";
+
+export type OutdatedGame = {
+  game: SupportedGame;
+  installedVersion: ChannelVersion | null;
+  expectedVersion: ChannelVersion;
+};
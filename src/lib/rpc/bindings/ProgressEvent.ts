@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ProgressStage } from "./ProgressStage";
+
+export type ProgressEvent = {
+  operationId: string;
+  stage: ProgressStage;
+  processedBytes: bigint;
+  totalBytes: bigint | null;
+  bytesPerSecond: bigint;
+  etaSeconds: bigint | null;
+};
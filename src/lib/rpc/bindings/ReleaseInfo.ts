@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ReleaseInfo = {
+  version: string;
+  publishedAt: string | null;
+  releaseUrl: string | null;
+  /**
+   * The asset for the current platform, if the release has one
+   */
+  downloadUrl: string | null;
+  prerelease: boolean;
+  invalid: boolean;
+  invalidationReasons: Array<string>;
+};
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BinaryProbe } from "./BinaryProbe";
+
+export type ToolingProbe = {
+  /**
+   * The version reported by the binaries, if any of them did
+   */
+  version: string | null;
+  binaries: Array<BinaryProbe>;
+};
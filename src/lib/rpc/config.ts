@@ -5,6 +5,10 @@ import { exists } from "@tauri-apps/plugin-fs";
 import { appDataDir, join } from "@tauri-apps/api/path";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import type { LauncherConfig } from "./bindings/LauncherConfig";
+import type { SettingsBackup } from "./bindings/SettingsBackup";
+import type { InstallProblem } from "./bindings/InstallProblem";
+import type { VersionChannel } from "./bindings/VersionChannel";
+import type { ChannelVersion } from "./bindings/ChannelVersion";
 
 export async function resetLauncherSettings(): Promise<string | null> {
   return await invoke_rpc("reset_to_defaults", {});
@@ -14,12 +18,41 @@ export async function getLauncherConfig(): Promise<LauncherConfig | null> {
   return await invoke_rpc("get_launcher_config", {});
 }
 
+export async function listSettingsBackups(): Promise<SettingsBackup[]> {
+  return await invoke_rpc("list_settings_backups", {}, () => []);
+}
+
+export async function restoreSettingsBackup(
+  fileName: string,
+): Promise<string | null> {
+  return await invoke_rpc2("restore_settings_backup", {
+    args: { fileName },
+  });
+}
+
 export async function setInstallationDirectory(
   newDir: string,
 ): Promise<string | null> {
   return await invoke_rpc("set_install_directory", { newDir: newDir });
 }
 
+export async function relocateInstallationDirectory(
+  newDir: string,
+  operationId: string | null = null,
+): Promise<string | null> {
+  return await invoke_rpc2("relocate_install_directory", {
+    args: { newDir, operationId },
+  });
+}
+
+export async function diagnoseInstall(): Promise<InstallProblem[]> {
+  return await invoke_rpc("diagnose_install", {}, () => []);
+}
+
+export async function repairInstall(): Promise<InstallProblem[]> {
+  return await invoke_rpc("repair_install", {}, () => []);
+}
+
 export async function isAVXRequirementMet(): Promise<boolean | undefined> {
   return await invoke_rpc("is_avx_requirement_met", {});
 }
@@ -50,8 +83,29 @@ export async function finalizeInstallation(
   });
 }
 
-export async function setActiveVersion(version: String): Promise<boolean> {
-  return invoke_rpc("set_active_version", { version: version }, () => true);
+export async function setGamePinnedVersion(
+  gameName: string,
+  version: string | null,
+  channel: VersionChannel = "official",
+): Promise<string | null> {
+  return await invoke_rpc2("set_game_pinned_version", {
+    args: { gameName, channel, version },
+  });
+}
+
+export async function setActiveVersion(
+  version: String,
+  channel: VersionChannel = "official",
+): Promise<boolean> {
+  return invoke_rpc("set_active_version", { channel, version }, () => true);
+}
+
+export async function getPreviousActiveVersion(): Promise<ChannelVersion | null> {
+  return await invoke_rpc("get_previous_active_version", {}, () => null);
+}
+
+export async function rollbackActiveVersion(): Promise<ChannelVersion | null> {
+  return await invoke_rpc("rollback_active_version", {}, () => null);
 }
 
 export async function localeSpecificFontAvailableForDownload(
@@ -161,6 +215,15 @@ export async function setAutoUninstallOldVersions(
   });
 }
 
+export async function setVersionsToKeep(count: number): Promise<void> {
+  return await invoke_rpc("set_versions_to_keep", { count });
+}
+
+/** `null` goes back to the official releases */
+export async function setReleaseIndexUrl(url: string | null): Promise<void> {
+  return await invoke_rpc("set_release_index_url", { url });
+}
+
 export async function setBypassRequirements(bypass: boolean): Promise<void> {
   return await invoke_rpc("set_bypass_requirements", {
     bypass: bypass,
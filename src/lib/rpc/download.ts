@@ -3,6 +3,7 @@ import { invoke_rpc } from "./rpc";
 export async function downloadFile(
   url: String,
   destination: String,
+  operationId: String | null = null,
 ): Promise<void> {
-  await invoke_rpc("download_file", { url, destination });
+  await invoke_rpc("download_file", { url, destination, operationId });
 }
@@ -1,5 +1,6 @@
 import { toastStore } from "$lib/stores/ToastStore";
 import type { ModInfo } from "./bindings/ModInfo";
+import type { ArchiveInspection } from "./bindings/ArchiveInspection";
 import type { ModSourceData } from "./bindings/ModSourceData";
 import { errorLog } from "./logging";
 import { invoke_rpc, invoke_rpc2 } from "./rpc";
@@ -18,11 +19,13 @@ export async function listExtractedTexturePackInfo(
 export async function extractNewTexturePack(
   gameName: string,
   pathToZip: string,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("extract_new_texture_pack", {
     args: {
       gameName: gameName,
       zipPath: pathToZip,
+      operationId,
     },
   });
 }
@@ -84,13 +87,20 @@ export async function removeModSource(url: string): Promise<void> {
   });
 }
 
+export async function inspectArchive(
+  archivePath: string,
+): Promise<ArchiveInspection | null> {
+  return await invoke_rpc("inspect_archive", { archivePath }, () => null);
+}
+
 export async function extractNewMod(
   gameName: string,
   bundlePath: string,
   modSource: string,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("extract_new_mod", {
-    args: { gameName, bundlePath, modSource },
+    args: { gameName, bundlePath, modSource, operationId },
   });
 }
 
@@ -100,9 +110,10 @@ export async function downloadAndExtractNewMod(
   downloadUrl: string,
   modName: string,
   sourceName: string,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("download_and_extract_new_mod", {
-    args: { gameName, downloadUrl, modName, sourceName },
+    args: { gameName, downloadUrl, modName, sourceName, operationId },
   });
 }
 
@@ -127,9 +138,10 @@ export async function extractIsoForModInstall(
   modName: string | undefined,
   sourceName: string | undefined,
   pathToIso: string,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("extract_iso_for_mod_install", {
-    args: { gameName, modName, sourceName, pathToIso },
+    args: { gameName, modName, sourceName, pathToIso, operationId },
   });
 }
 
@@ -137,9 +149,10 @@ export async function decompileForModInstall(
   gameName: string,
   modName: string | undefined,
   sourceName: string | undefined,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("decompile_for_mod_install", {
-    args: { gameName, modName, sourceName },
+    args: { gameName, modName, sourceName, operationId },
   });
 }
 
@@ -147,9 +160,10 @@ export async function compileForModInstall(
   gameName: string,
   modName: string | undefined,
   sourceName: string | undefined,
+  operationId: string | null = null,
 ): Promise<string | null> {
   return await invoke_rpc2("compile_for_mod_install", {
-    args: { gameName, modName, sourceName },
+    args: { gameName, modName, sourceName, operationId },
   });
 }
 
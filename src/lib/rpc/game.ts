@@ -1,5 +1,7 @@
 import { invoke_rpc } from "./rpc";
 import { toastStore } from "$lib/stores/ToastStore";
+import type { OutdatedGame } from "./bindings/OutdatedGame";
+import type { OutdatedGamesUpdate } from "./bindings/OutdatedGamesUpdate";
 
 export async function uninstallGame(gameName: string): Promise<boolean> {
   return await invoke_rpc("uninstall_game", { gameName });
@@ -16,3 +18,13 @@ export async function getFurthestGameMilestone(
 ): Promise<String> {
   return await invoke_rpc("get_furthest_game_milestone", { gameName });
 }
+
+export async function listOutdatedGames(): Promise<OutdatedGame[]> {
+  return await invoke_rpc("list_outdated_games", {}, () => []);
+}
+
+export async function updateOutdatedGames(): Promise<
+  OutdatedGamesUpdate | undefined
+> {
+  return await invoke_rpc("update_outdated_games", {});
+}
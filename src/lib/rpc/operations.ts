@@ -0,0 +1,6 @@
+import { invoke_rpc } from "./rpc";
+
+/** Returns false if the operation had already finished */
+export async function cancelOperation(operationId: string): Promise<boolean> {
+  return await invoke_rpc("cancel_operation", { operationId }, () => false);
+}
@@ -0,0 +1,31 @@
+import type { InstallProfileInfo } from "./bindings/InstallProfileInfo";
+import { invoke_rpc, invoke_rpc2 } from "./rpc";
+
+export async function listInstallProfiles(): Promise<InstallProfileInfo[]> {
+  return await invoke_rpc("list_install_profiles", {}, () => []);
+}
+
+export async function createInstallProfile(
+  name: string,
+  installationDir?: string,
+): Promise<string | null> {
+  return await invoke_rpc2("create_install_profile", {
+    args: { name, installationDir: installationDir ?? null },
+  });
+}
+
+export async function switchInstallProfile(
+  name: string,
+): Promise<string | null> {
+  return await invoke_rpc2("switch_install_profile", {
+    args: { name },
+  });
+}
+
+export async function deleteInstallProfile(
+  name: string,
+): Promise<string | null> {
+  return await invoke_rpc2("delete_install_profile", {
+    args: { name },
+  });
+}
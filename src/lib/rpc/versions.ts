@@ -1,42 +1,83 @@
-import { getLatestOfficialRelease } from "$lib/utils/github";
 import { invoke_rpc } from "./rpc";
 import { config } from "/src/state/config.svelte";
+import type { ReleaseInfo } from "./bindings/ReleaseInfo";
+import type { RetentionPlan } from "./bindings/RetentionPlan";
+import type { ToolingProbe } from "./bindings/ToolingProbe";
+import type { VersionChannel } from "./bindings/VersionChannel";
 
-export async function listDownloadedVersions(): Promise<string[]> {
-  return await invoke_rpc(
-    "list_downloaded_versions",
-    { versionFolder: "official" },
-    () => [],
-  );
+export async function listDownloadedVersions(
+  channel: VersionChannel = "official",
+): Promise<string[]> {
+  return await invoke_rpc("list_downloaded_versions", { channel }, () => []);
+}
+
+export async function listAvailableVersions(
+  refresh: boolean = false,
+): Promise<ReleaseInfo[]> {
+  return await invoke_rpc("list_available_versions", { refresh }, () => []);
+}
+
+export async function getLatestVersion(
+  refresh: boolean = false,
+): Promise<ReleaseInfo | null> {
+  return await invoke_rpc("get_latest_version", { refresh }, () => null);
 }
 
 export async function downloadOfficialVersion(
   version: String,
   url: String,
+  checksum: String | null = null,
+  operationId: String | null = null,
 ): Promise<boolean> {
   return await invoke_rpc(
     "download_version",
-    { version, url, versionFolder: "official" },
+    { version, url, channel: "official", checksum, operationId },
     () => true,
   );
 }
 
-export async function removeVersion(version: String): Promise<boolean> {
-  return await invoke_rpc("remove_version", { version }, () => true);
+/**
+ * Registers a locally built version (a build directory or an archive of one)
+ * in the `local` channel, returning the name it was registered under
+ */
+export async function importLocalVersion(
+  source: string,
+  name: string | null = null,
+): Promise<string | null> {
+  return await invoke_rpc("import_local_version", { source, name }, () => null);
+}
+
+/** Runs the version's binaries to check they work on this machine */
+export async function probeToolingVersion(
+  version: string,
+  channel: VersionChannel = "official",
+): Promise<ToolingProbe | null> {
+  return await invoke_rpc(
+    "probe_tooling_version",
+    { channel, version },
+    () => null,
+  );
+}
+
+export async function removeVersion(
+  version: String,
+  channel: VersionChannel = "official",
+): Promise<boolean> {
+  return await invoke_rpc("remove_version", { channel, version }, () => true);
+}
+
+/** Lists what `removeOldVersions` would remove, without removing anything */
+export async function previewVersionRetention(): Promise<RetentionPlan> {
+  return await invoke_rpc("preview_version_retention", {}, () => ({
+    versions: [],
+    freedBytes: BigInt(0),
+  }));
 }
 
 export async function removeOldVersions(): Promise<boolean> {
   const shouldRemove = config?.deletePreviousVersions;
   if (shouldRemove) {
-    let downloadedVersions = await listDownloadedVersions();
-    let latestRelease = await getLatestOfficialRelease();
-    downloadedVersions = downloadedVersions.filter(
-      (v) => v !== latestRelease?.version,
-    );
-    downloadedVersions.forEach((v) => {
-      removeVersion(v);
-    });
-    return false;
+    await invoke_rpc("apply_version_retention", {}, () => null);
   }
   return false;
 }
@@ -77,7 +77,7 @@ export const { p, navigate, isActive, route } = createRouter({
             return;
           }
 
-          versionState.activeToolingVersion = config.activeVersion;
+          versionState.activeToolingVersion = config.activeVersion?.version;
           if (!config.activeVersion) {
             throw navigate("/:game_name/tools-not-set", {
               params: { game_name: params.game_name },
@@ -110,7 +110,8 @@ export const { p, navigate, isActive, route } = createRouter({
             });
           }
 
-          const installedVersion = config?.games?.[activeGame]?.version;
+          const installedVersion =
+            config?.games?.[activeGame]?.version?.version;
           if (installedVersion !== versionState.activeToolingVersion) {
             throw navigate("/:game_name/update", {
               params: { game_name: params.game_name },